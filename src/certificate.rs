@@ -1,55 +1,81 @@
 /*
  */
 use crate::crypto;
+use crate::crypto::extensions::{CertificatePolicy, CustomExtension};
+use crate::crypto::issuer_urls::IssuerUrls;
+use crate::crypto::name::{self, Attribute, DistinguishedName, StringEncoding};
+use crate::crypto::name_constraints::{NameConstraints, Subtree};
+use crate::crypto::profile::Profile;
+use crate::crypto::request::CertificateRequest;
+use crate::crypto::san::SubjectAltName;
+use crate::passphrase;
 use crate::Error;
+use chrono::{DateTime, Datelike, Duration, SecondsFormat, Utc};
 use clap::ArgMatches;
 use colored::*;
+use openssl::x509::{X509Ref, X509};
 use std::fs;
 use std::io;
 use std::io::Write;
 use std::ops::Div;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
 pub struct Config {
     certificate_path: PathBuf,
+    /// The existing CA certificate whose subject and public key are cross-signed.
+    cross_signed_certificate: Option<X509>,
+    cross_signed_certificate_path: Option<PathBuf>,
     crypto_config: crypto::Config,
+    encrypt_private_key: bool,
+    existing_public_key_path: Option<PathBuf>,
     is_ca: bool,
+    /// The issuer URLs of the created CA, written next to its certificate for the certificates it
+    /// issues.
+    issued_issuer_urls: IssuerUrls,
+    issued_issuer_urls_path: Option<PathBuf>,
     no_input: bool,
     output_directory: PathBuf,
-    private_key_path: PathBuf,
+    private_key_path: Option<PathBuf>,
+    public_key_path: Option<PathBuf>,
+    request_path: Option<PathBuf>,
+    requested_extensions: Vec<CustomExtension>,
+    requested_subject_alt_names: Vec<SubjectAltName>,
+    /// The number of requested subject alternative names that are skipped as they are not copied
+    /// and of an unsupported type.
+    requested_unsupported_subject_alt_names: usize,
+    /// The signer's issuer URL file, if it has one.
+    signer_issuer_urls_path: Option<PathBuf>,
+    signer_not_after: Option<DateTime<Utc>>,
+    validity_clamped: bool,
 }
 
 pub fn run(clap_matches: &ArgMatches, is_ca: bool) -> Result<(), Error> {
     match create_config(clap_matches, is_ca) {
         Ok(config) => {
-            let certificate = match &config.is_ca {
-                true => crypto::create_ca_certificate(&config.crypto_config),
-                false => crypto::create_certificate(&config.crypto_config),
-            };
-
-            match certificate {
-                Ok((certificate_bytes, private_key_bytes)) => {
-                    fs::write(&config.certificate_path, certificate_bytes)?;
-                    fs::write(&config.private_key_path, private_key_bytes)?;
-
-                    if config.no_input {
-                        println!(
-                            "{} {}",
-                            "created".green(),
-                            config.private_key_path.display()
-                        );
-                        println!(
-                            "{} {}",
-                            "created".green(),
-                            config.certificate_path.display()
-                        );
-                    }
-
-                    Ok(())
+            let credentials = match (&config.is_ca, &config.cross_signed_certificate) {
+                (true, Some(cross_signed_certificate)) => {
+                    crypto::cross_sign_certificate(&config.crypto_config, cross_signed_certificate)
                 }
-                Err(err) => Err(Error::Crypto(err)),
-            }
+                (true, None) => crypto::create_ca_certificate(&config.crypto_config),
+                (false, _) => crypto::create_certificate(&config.crypto_config),
+            }?;
+
+            write_outputs(
+                vec![
+                    (config.private_key_path.as_ref(), credentials.private_key),
+                    (config.public_key_path.as_ref(), credentials.public_key),
+                    (
+                        Some(&config.certificate_path),
+                        Some(credentials.certificate),
+                    ),
+                    (
+                        config.issued_issuer_urls_path.as_ref(),
+                        Some(config.issued_issuer_urls.to_file().into_bytes()),
+                    ),
+                ],
+                config.no_input,
+            )
         }
         Err(Error::CreateAborted) => Ok(()),
         Err(err) => Err(err),
@@ -57,15 +83,94 @@ pub fn run(clap_matches: &ArgMatches, is_ca: bool) -> Result<(), Error> {
 }
 
 fn create_config(clap_matches: &ArgMatches, is_ca: bool) -> Result<Config, Error> {
-    let uuid = &Uuid::new_v4().to_hyphenated().to_string()[..];
+    let request_path = match clap_matches.value_of("csr") {
+        Some(request_path_str) => Some(PathBuf::from(request_path_str).canonicalize()?),
+        None => None,
+    };
+
+    let request = match &request_path {
+        Some(request_path) => Some(CertificateRequest::load(request_path)?),
+        None => None,
+    };
 
-    let common_name = clap_matches
-        .value_of("common-name")
-        .or(Some(uuid))
-        .unwrap()
-        .to_string();
+    let cross_signed_certificate_path = match clap_matches.value_of("certificate") {
+        Some(certificate_path_str) => Some(PathBuf::from(certificate_path_str).canonicalize()?),
+        None => None,
+    };
+
+    let cross_signed_certificate = match &cross_signed_certificate_path {
+        Some(certificate_path) => {
+            let certificate = crypto::load_certificate(certificate_path)?;
+
+            if !crypto::is_ca(&certificate) {
+                return Err(Error::CrossSignedCertificateNotCa(certificate_path.clone()));
+            }
 
-    let days_valid: u32 = clap_matches.value_of_t("days-valid").unwrap();
+            Some(certificate)
+        }
+        None => None,
+    };
+
+    // The requested subject is used unless it is overridden with --subject. A cross-signed
+    // certificate's subject is copied as it is, whatever attributes it has.
+    let subject = match (
+        &cross_signed_certificate,
+        &request,
+        clap_matches.value_of("subject"),
+    ) {
+        (Some(_), _, _) => None,
+        (None, Some(request), None) => Some(request.subject(resolve_name_encoding(
+            clap_matches.value_of("name-encoding"),
+        ))?),
+        _ => Some(resolve_subject(clap_matches)?),
+    };
+
+    // Files are named after the common name, which a cross-signed certificate's subject may lack.
+    let file_stem = match (&subject, &cross_signed_certificate) {
+        (Some(subject), _) => subject.file_stem()?,
+        (None, Some(certificate)) => certificate_file_stem(certificate)?,
+        (None, None) => unreachable!("only cross-signed certificates copy their subject"),
+    };
+
+    let name_constraints = match is_ca {
+        true => resolve_name_constraints(clap_matches)?,
+        false => NameConstraints::default(),
+    };
+
+    // Requested names of an unsupported type are never copied.
+    let (requested_subject_alt_names, requested_unsupported_subject_alt_names) = match &request {
+        Some(request) => request.supported_subject_alt_names()?,
+        None => (Vec::new(), 0),
+    };
+
+    let copied_subject_alt_names =
+        resolve_copied_subject_alt_names(clap_matches, &requested_subject_alt_names)?;
+
+    let requested_extensions = match &request {
+        Some(request) => request.extensions()?,
+        None => Vec::new(),
+    };
+
+    let mut subject_alt_names = match is_ca {
+        true => Vec::new(),
+        false => resolve_subject_alt_names(
+            clap_matches,
+            subject
+                .as_ref()
+                .and_then(|subject| subject.common_name())
+                .unwrap(),
+        )?,
+    };
+
+    for name in copied_subject_alt_names {
+        if !subject_alt_names.contains(&name) {
+            subject_alt_names.push(name);
+        }
+    }
+
+    let custom_extensions = resolve_custom_extensions(clap_matches, &requested_extensions)?;
+
+    let mut validity = resolve_validity(clap_matches)?;
 
     let output_directory = PathBuf::from(
         clap_matches
@@ -77,27 +182,76 @@ fn create_config(clap_matches: &ArgMatches, is_ca: bool) -> Result<Config, Error
 
     let output_format = resolve_output_format(clap_matches.value_of("output-format"));
 
-    let prefix = match is_ca {
-        true => "-ca",
-        false => "",
+    // Cross-signed certificates are written next to, not over, the certificate they copy.
+    let prefix = match (is_ca, &cross_signed_certificate) {
+        (true, Some(_)) => "-ca-cross-signed",
+        (true, None) => "-ca",
+        (false, _) => "",
     };
 
     let cert_filename = format!(
         "{}{}-certificate.{}",
-        common_name,
+        file_stem,
         prefix,
         output_format.to_string().to_lowercase()
     );
 
     let key_filename = format!(
         "{}{}-private-key.{}",
-        common_name,
+        file_stem,
         prefix,
         output_format.to_string().to_lowercase()
     );
 
     let certificate_path = output_directory.join(cert_filename);
-    let private_key_path = output_directory.join(key_filename);
+
+    let export_public_key = clap_matches.is_present("export-public-key");
+
+    let public_key_path = match export_public_key {
+        true => Some(output_directory.join(format!(
+            "{}{}-public-key.{}",
+            file_stem,
+            prefix,
+            output_format.to_string().to_lowercase()
+        ))),
+        false => None,
+    };
+
+    let existing_private_key_path = match clap_matches.value_of("private-key-path") {
+        Some(existing_private_key_path_str) => {
+            Some(PathBuf::from(existing_private_key_path_str).canonicalize()?)
+        }
+        None => None,
+    };
+
+    let existing_public_key_path = match clap_matches.value_of("public-key-path") {
+        Some(existing_public_key_path_str) => {
+            Some(PathBuf::from(existing_public_key_path_str).canonicalize()?)
+        }
+        None => None,
+    };
+
+    let subject_key = match (
+        &cross_signed_certificate,
+        &request,
+        &existing_public_key_path,
+        existing_private_key_path,
+    ) {
+        (Some(certificate), _, _, _) => crypto::SubjectKey::PublicKey(certificate.public_key()?),
+        (None, Some(request), _, _) => crypto::SubjectKey::PublicKey(request.public_key().clone()),
+        (None, None, Some(existing_public_key_path), _) => {
+            crypto::SubjectKey::PublicKey(crypto::load_public_key(existing_public_key_path)?)
+        }
+        (None, None, None, Some(existing_private_key_path)) => {
+            crypto::SubjectKey::PrivateKey(existing_private_key_path)
+        }
+        (None, None, None, None) => crypto::SubjectKey::Generate(resolve_key_type(clap_matches)?),
+    };
+
+    let private_key_path = match subject_key {
+        crypto::SubjectKey::Generate(_) => Some(output_directory.join(key_filename)),
+        _ => None,
+    };
 
     let signer_private_key_path: Option<PathBuf>;
     let signer_certificate_path: Option<PathBuf>;
@@ -119,59 +273,507 @@ fn create_config(clap_matches: &ArgMatches, is_ca: bool) -> Result<Config, Error
 
     let self_signed = signer_certificate_path.is_none() || signer_private_key_path.is_none();
 
-    let config = Config {
+    let (signer_issuer_urls_path, signer_issuer_urls) = match &signer_certificate_path {
+        Some(signer_certificate_path) => load_signer_issuer_urls(signer_certificate_path)?,
+        None => (None, IssuerUrls::default()),
+    };
+
+    let issued_issuer_urls = match is_ca {
+        true => resolve_issuer_urls(clap_matches, "issued-", &IssuerUrls::default())?,
+        false => IssuerUrls::default(),
+    };
+
+    let issued_issuer_urls_path = match issued_issuer_urls.is_empty() {
+        true => None,
+        false => Some(IssuerUrls::file_path(&certificate_path)),
+    };
+
+    let path_length = resolve_path_length(
+        match clap_matches.is_present("path-length") {
+            true => Some(
+                clap_matches
+                    .value_of_t("path-length")
+                    .unwrap_or_else(|err| err.exit()),
+            ),
+            false => None,
+        },
+        cross_signed_certificate.as_ref(),
+        self_signed,
+    );
+
+    let signer_not_after = match &signer_certificate_path {
+        Some(signer_certificate_path) => Some(resolve_signer_not_after(signer_certificate_path)?),
+        None => None,
+    };
+
+    let validity_clamped = match signer_not_after {
+        Some(signer_not_after) => clamp_to_signer(
+            &mut validity,
+            signer_not_after,
+            clap_matches.is_present("clamp-to-signer"),
+        )?,
+        None => false,
+    };
+
+    let mut config = Config {
         certificate_path,
+        cross_signed_certificate,
+        cross_signed_certificate_path,
+        encrypt_private_key: clap_matches.is_present("encrypt-private-key"),
+        existing_public_key_path,
         crypto_config: crypto::Config {
+            certificate_policies: resolve_certificate_policies(clap_matches)?,
+            custom_extensions,
             self_signed,
             signer_certificate_path,
+            signer_private_key_passphrase: None,
             signer_private_key_path,
-            common_name,
-            key_type: resolve_key_type(clap_matches.value_of("key-type")),
-            days_valid,
+            existing_private_key_passphrase: None,
+            export_public_key,
+            issuer_urls: resolve_issuer_urls(clap_matches, "", &signer_issuer_urls)?,
+            key_encoding: resolve_key_encoding(clap_matches.value_of("key-encoding")),
+            name_constraints,
             output_format,
+            path_length,
+            private_key_passphrase: None,
+            profile: resolve_profile(clap_matches.value_of("profile")),
+            signature_hash: resolve_signature_hash(clap_matches.value_of("signature-hash")),
+            signature_scheme: resolve_signature_scheme(clap_matches.value_of("signature-scheme")),
+            subject,
+            subject_alt_names,
+            subject_key,
+            validity,
         },
         is_ca,
+        issued_issuer_urls,
+        issued_issuer_urls_path,
         no_input: clap_matches.is_present("no-input"),
         output_directory,
         private_key_path,
+        public_key_path,
+        request_path,
+        requested_extensions,
+        requested_subject_alt_names,
+        requested_unsupported_subject_alt_names,
+        signer_issuer_urls_path,
+        signer_not_after,
+        validity_clamped,
     };
 
+    check_key_encoding(
+        &config.crypto_config.key_encoding,
+        generated_key_type(&config.crypto_config.subject_key),
+        config.encrypt_private_key,
+    )?;
+
     if !config.no_input {
         verify_config(&config)?;
     }
 
+    if let Some(signer_private_key_path) = &config.crypto_config.signer_private_key_path {
+        config.crypto_config.signer_private_key_passphrase = resolve_signer_private_key_passphrase(
+            clap_matches,
+            signer_private_key_path,
+            config.no_input,
+        )?;
+    }
+
+    if let crypto::SubjectKey::PrivateKey(private_key_path) = &config.crypto_config.subject_key {
+        config.crypto_config.existing_private_key_passphrase =
+            resolve_existing_private_key_passphrase(
+                clap_matches,
+                private_key_path,
+                config.no_input,
+            )?;
+    }
+
+    config.crypto_config.private_key_passphrase =
+        resolve_private_key_passphrase(clap_matches, config.no_input)?;
+
     Ok(config)
 }
 
+/// Build the subject from either an RFC 4514 string or the individual attribute options.
+pub fn resolve_subject(clap_matches: &ArgMatches) -> Result<DistinguishedName, Error> {
+    let uuid = &Uuid::new_v4().to_hyphenated().to_string()[..];
+
+    let name_encoding = resolve_name_encoding(clap_matches.value_of("name-encoding"));
+
+    match clap_matches.value_of("subject") {
+        Some(raw_subject) => DistinguishedName::parse(raw_subject, name_encoding),
+        None => {
+            let mut attributes = vec![(
+                Attribute::CommonName,
+                clap_matches
+                    .value_of("common-name")
+                    .unwrap_or(uuid)
+                    .to_string(),
+            )];
+
+            for (arg, attribute) in &[
+                ("organization", Attribute::Organization),
+                ("organizational-unit", Attribute::OrganizationalUnit),
+                ("country", Attribute::Country),
+                ("state", Attribute::State),
+                ("locality", Attribute::Locality),
+                ("subject-serial-number", Attribute::SerialNumber),
+                ("email-address", Attribute::EmailAddress),
+            ] {
+                if let Some(value) = clap_matches.value_of(arg) {
+                    attributes.push((*attribute, value.to_string()));
+                }
+            }
+
+            DistinguishedName::from_attributes(attributes, name_encoding)
+        }
+    }
+}
+
+/// Refuse key encodings that cannot be written: traditional encodings cannot be encrypted and only
+/// exist for EC and RSA keys. Only generated keys are written.
+pub fn check_key_encoding(
+    key_encoding: &crypto::KeyEncoding,
+    generated_key_type: Option<&crypto::KeyType>,
+    encrypt_private_key: bool,
+) -> Result<(), Error> {
+    if let crypto::KeyEncoding::Traditional = key_encoding {
+        if encrypt_private_key {
+            return Err(Error::EncryptedTraditionalKeyEncoding);
+        }
+
+        if let Some(key_type) = generated_key_type {
+            if !key_type.has_traditional_encoding() {
+                return Err(Error::TraditionalKeyEncodingUnavailable);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The type of the key pair to generate, or None when the certificate is created for an existing
+/// key.
+fn generated_key_type(subject_key: &crypto::SubjectKey) -> Option<&crypto::KeyType> {
+    match subject_key {
+        crypto::SubjectKey::Generate(key_type) => Some(key_type),
+        _ => None,
+    }
+}
+
+/// The base of the file names created for an existing certificate: a value of its subject, or its
+/// serial number when the subject has none.
+pub fn certificate_file_stem(certificate: &X509Ref) -> Result<String, Error> {
+    match name::x509_name_file_stem(certificate.subject_name()) {
+        Some(file_stem) => Ok(file_stem),
+        None => Ok(certificate
+            .serial_number()
+            .to_bn()?
+            .to_hex_str()?
+            .to_lowercase()),
+    }
+}
+
+/// Load the signer certificate and make sure it can sign certificates, returning when it expires.
+pub fn resolve_signer_not_after(signer_certificate_path: &Path) -> Result<DateTime<Utc>, Error> {
+    let signer_certificate = crypto::load_certificate(signer_certificate_path)?;
+    crypto::check_signer_certificate(&signer_certificate)?;
+    Ok(crypto::to_timestamp(signer_certificate.not_after())?)
+}
+
+/// The passphrase of the signer private key, if it is encrypted.
+pub fn resolve_signer_private_key_passphrase(
+    clap_matches: &ArgMatches,
+    signer_private_key_path: &Path,
+    no_input: bool,
+) -> Result<Option<String>, Error> {
+    match crypto::private_key_is_encrypted(signer_private_key_path)? {
+        true => Ok(Some(passphrase::resolve(
+            &passphrase::Source {
+                file_arg: "signer-passphrase-file",
+                file_path: clap_matches.value_of("signer-passphrase-file"),
+                env_var: "TCH_SIGNER_PASSPHRASE",
+                prompt: "signer private key passphrase:",
+                confirm: false,
+            },
+            no_input,
+        )?)),
+        false => Ok(None),
+    }
+}
+
+/// The passphrase of the existing private key the certificate is created for, if it is encrypted.
+pub fn resolve_existing_private_key_passphrase(
+    clap_matches: &ArgMatches,
+    private_key_path: &Path,
+    no_input: bool,
+) -> Result<Option<String>, Error> {
+    match crypto::private_key_is_encrypted(private_key_path)? {
+        true => Ok(Some(passphrase::resolve(
+            &passphrase::Source {
+                file_arg: "private-key-passphrase-file",
+                file_path: clap_matches.value_of("private-key-passphrase-file"),
+                env_var: "TCH_PRIVATE_KEY_PASSPHRASE",
+                prompt: "private key passphrase:",
+                confirm: false,
+            },
+            no_input,
+        )?)),
+        false => Ok(None),
+    }
+}
+
+/// The passphrase to encrypt the created private key with, if encryption was requested.
+pub fn resolve_private_key_passphrase(
+    clap_matches: &ArgMatches,
+    no_input: bool,
+) -> Result<Option<String>, Error> {
+    match clap_matches.is_present("encrypt-private-key") {
+        true => Ok(Some(passphrase::resolve(
+            &passphrase::Source {
+                file_arg: "passphrase-file",
+                file_path: clap_matches.value_of("passphrase-file"),
+                env_var: "TCH_PASSPHRASE",
+                prompt: "private key passphrase:",
+                confirm: true,
+            },
+            no_input,
+        )?)),
+        false => Ok(None),
+    }
+}
+
 fn verify_config(config: &Config) -> Result<(), Error> {
     let output_directory = config.output_directory.as_path().to_str().unwrap().cyan();
 
+    // Key generation options do not apply when an existing key is used.
+    let generated_key_type = generated_key_type(&config.crypto_config.subject_key);
+
+    let existing_private_key_path = match &config.crypto_config.subject_key {
+        crypto::SubjectKey::PrivateKey(private_key_path) => Some(private_key_path),
+        _ => None,
+    };
+
+    // A cross-signed certificate's subject is copied, its attributes are shown as they are.
+    let (common_name, subject, name_encoding) = match (
+        &config.crypto_config.subject,
+        &config.cross_signed_certificate,
+    ) {
+        (Some(subject), _) => (
+            subject.common_name().unwrap().to_string(),
+            subject.to_string(),
+            subject.encoding.to_string(),
+        ),
+        (None, Some(certificate)) => (
+            "not applicable".to_string(),
+            name::x509_name_to_string(certificate.subject_name()),
+            "not applicable".to_string(),
+        ),
+        (None, None) => unreachable!("only cross-signed certificates copy their subject"),
+    };
+
+    let name_constraints_copied = match &config.cross_signed_certificate {
+        Some(certificate) => {
+            crypto::copied_name_constraints(&config.crypto_config, certificate).is_some()
+        }
+        None => false,
+    };
+
+    let created = match (
+        config.is_ca,
+        &config.cross_signed_certificate_path,
+        &config.request_path,
+    ) {
+        (true, Some(_), _) => "A cross-signed CA certificate",
+        (true, None, _) => "A CA certificate",
+        (false, _, Some(_)) => "A certificate for the certificate signing request",
+        (false, _, None) => "A certificate",
+    };
+
     print!(
         "\
-A CA certificate and private key will be created using the following configuration.
+{}{} will be created using the following configuration.
 
+cross-signed certificate path: {}
+private key path: {}
+public key path: {}
+certificate request path: {}
+encrypt private key: {}
+key encoding: {}
 key type: {}
+curve: {}
+key size: {}
+public exponent: {}
+signature hash: {}
+signature scheme: {}
+profile: {}
+path length: {}
+permitted subtrees: {}
+excluded subtrees: {}
 common name: {}
-days valid: {} (which is {} years)
+subject: {}
+name encoding: {}
+requested subject alternative names: {}
+requested extensions: {}
+subject alternative names: {}
+crl distribution points: {}
+ocsp urls: {}
+ca issuers urls: {}
+signer issuer urls file: {}
+issued crl distribution points: {}
+issued ocsp urls: {}
+issued ca issuers urls: {}
+certificate policies: {}
+custom extensions: {}
+not before: {}
+not after: {}
 self-signed: {}
 signer certificate path: {}
 signer private key path: {}
+signer not after: {}
 output directory: {}
 
-{} Double check \"days valid\" above. Inconsiderate values can have devastating consequences.
+{} Double check \"not after\" above. Inconsiderate values can have devastating consequences.
 ",
+        created,
+        match config.private_key_path {
+            Some(_) => " and private key",
+            None => "",
+        },
         config
-            .crypto_config
-            .key_type
+            .cross_signed_certificate_path
+            .as_ref()
+            .unwrap_or(&PathBuf::from("not applicable"))
+            .display()
+            .to_string()
+            .cyan(),
+        existing_private_key_path
+            .unwrap_or(&PathBuf::from("not applicable"))
+            .display()
             .to_string()
-            .to_lowercase()
             .cyan(),
-        config.crypto_config.common_name.cyan(),
-        config.crypto_config.days_valid.to_string().cyan(),
-        (config.crypto_config.days_valid as f32)
-            .div(365.0)
+        config
+            .existing_public_key_path
+            .as_ref()
+            .unwrap_or(&PathBuf::from("not applicable"))
+            .display()
             .to_string()
             .cyan(),
+        config
+            .request_path
+            .as_ref()
+            .unwrap_or(&PathBuf::from("not applicable"))
+            .display()
+            .to_string()
+            .cyan(),
+        config.encrypt_private_key.to_string().cyan(),
+        config.crypto_config.key_encoding.to_string().cyan(),
+        generated_key_type
+            .map(|key_type| key_type.to_string().to_lowercase())
+            .unwrap_or_else(|| "not applicable".to_string())
+            .cyan(),
+        match generated_key_type {
+            Some(crypto::KeyType::Ec(curve)) => curve.to_string(),
+            _ => "not applicable".to_string(),
+        }
+        .cyan(),
+        match generated_key_type {
+            Some(crypto::KeyType::Rsa(rsa_parameters))
+            | Some(crypto::KeyType::RsaPss(rsa_parameters)) => {
+                rsa_parameters.key_size.to_string()
+            }
+            _ => "not applicable".to_string(),
+        }
+        .cyan(),
+        match generated_key_type {
+            Some(crypto::KeyType::Rsa(rsa_parameters))
+            | Some(crypto::KeyType::RsaPss(rsa_parameters)) => {
+                rsa_parameters.public_exponent.to_string()
+            }
+            _ => "not applicable".to_string(),
+        }
+        .cyan(),
+        config
+            .crypto_config
+            .signature_hash
+            .map(|signature_hash| signature_hash.to_string())
+            .unwrap_or_else(|| "matching the signing key".to_string())
+            .cyan(),
+        config.crypto_config.signature_scheme.to_string().cyan(),
+        match config.is_ca {
+            true => "not applicable".to_string(),
+            false => config.crypto_config.profile.to_string(),
+        }
+        .cyan(),
+        match (config.is_ca, config.crypto_config.path_length) {
+            (false, _) => "not applicable".to_string(),
+            (true, Some(path_length)) => path_length.to_string(),
+            (true, None) => "unlimited".to_string(),
+        }
+        .cyan(),
+        match name_constraints_copied {
+            true => "copied from the cross-signed certificate".to_string(),
+            false => format_subtrees(
+                config.is_ca,
+                &config.crypto_config.name_constraints.permitted
+            ),
+        }
+        .cyan(),
+        match name_constraints_copied {
+            true => "copied from the cross-signed certificate".to_string(),
+            false => format_subtrees(
+                config.is_ca,
+                &config.crypto_config.name_constraints.excluded
+            ),
+        }
+        .cyan(),
+        common_name.cyan(),
+        subject.cyan(),
+        name_encoding.cyan(),
+        match config.request_path {
+            Some(_) => format_subject_alt_names(
+                &config.requested_subject_alt_names,
+                config.requested_unsupported_subject_alt_names,
+                "skipped",
+            ),
+            None => "not applicable".to_string(),
+        }
+        .cyan(),
+        match config.request_path {
+            Some(_) => format_list(&config.requested_extensions),
+            None => "not applicable".to_string(),
+        }
+        .cyan(),
+        match config.crypto_config.subject_alt_names.is_empty() {
+            true => "none".to_string(),
+            false => config
+                .crypto_config
+                .subject_alt_names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<String>>()
+                .join(", "),
+        }
+        .cyan(),
+        format_list(&config.crypto_config.issuer_urls.crl_distribution_points).cyan(),
+        format_list(&config.crypto_config.issuer_urls.ocsp).cyan(),
+        format_list(&config.crypto_config.issuer_urls.ca_issuers).cyan(),
+        config
+            .signer_issuer_urls_path
+            .as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| "none".to_string())
+            .cyan(),
+        format_issued_urls(
+            config.is_ca,
+            &config.issued_issuer_urls.crl_distribution_points
+        )
+        .cyan(),
+        format_issued_urls(config.is_ca, &config.issued_issuer_urls.ocsp).cyan(),
+        format_issued_urls(config.is_ca, &config.issued_issuer_urls.ca_issuers).cyan(),
+        format_list(&config.crypto_config.certificate_policies).cyan(),
+        format_list(&config.crypto_config.custom_extensions).cyan(),
+        format_timestamp(config.crypto_config.validity.not_before).cyan(),
+        format_not_after(&config.crypto_config.validity).cyan(),
         config.crypto_config.self_signed.to_string().cyan(),
         config
             .crypto_config
@@ -189,10 +791,22 @@ output directory: {}
             .display()
             .to_string()
             .cyan(),
+        config
+            .signer_not_after
+            .map(format_timestamp)
+            .unwrap_or_else(|| "not applicable".to_string())
+            .cyan(),
         output_directory,
         "WARNING".yellow(),
     );
 
+    if config.validity_clamped {
+        println!(
+            "{} The validity period was shortened to end with the signer certificate's.",
+            "WARNING".yellow()
+        );
+    }
+
     if config.crypto_config.self_signed {
         println!(
             "{} You are creating a self-signed certificate.",
@@ -200,9 +814,39 @@ output directory: {}
         );
     }
 
+    confirm(&[
+        config.private_key_path.as_ref(),
+        config.public_key_path.as_ref(),
+        Some(&config.certificate_path),
+        config.issued_issuer_urls_path.as_ref(),
+    ])
+}
+
+/// Write the outputs that have a path and contents. In no-input mode, where no preview listed them,
+/// the written files are listed afterwards.
+pub fn write_outputs(
+    outputs: Vec<(Option<&PathBuf>, Option<Vec<u8>>)>,
+    no_input: bool,
+) -> Result<(), Error> {
+    for (path, contents) in outputs {
+        if let (Some(path), Some(contents)) = (path, contents) {
+            fs::write(path, contents)?;
+
+            if no_input {
+                println!("{} {}", "created".green(), path.display());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// List the files that will be created and ask whether to go ahead, ending the preview.
+pub fn confirm(paths: &[Option<&PathBuf>]) -> Result<(), Error> {
     println!();
-    println!("{} {}", "create".green(), config.private_key_path.display());
-    println!("{} {}", "create".green(), config.certificate_path.display());
+    for path in paths.iter().flatten() {
+        println!("{} {}", "create".green(), path.display());
+    }
     println!();
     print!("{} ", "execute (Y/n):".magenta());
     let mut guess = String::new();
@@ -219,7 +863,211 @@ output directory: {}
     }
 }
 
-fn resolve_output_format(raw_output_format: Option<&str>) -> crypto::FileFormat {
+/// The end of the validity period along with its length, for the preview.
+pub fn format_not_after(validity: &crypto::Validity) -> String {
+    match validity.not_after {
+        Some(not_after) => format!(
+            "{} (which is {:.2} years)",
+            format_timestamp(not_after),
+            ((not_after - validity.not_before).num_seconds() as f32)
+                .div(365.0 * 24.0 * 60.0 * 60.0)
+        ),
+        None => format!(
+            "{} (no well-defined expiration date)",
+            crypto::NO_WELL_DEFINED_EXPIRY
+        ),
+    }
+}
+
+/// Like format_list, followed by how many names of an unsupported type were left out and how they
+/// are treated.
+pub fn format_subject_alt_names(
+    names: &[SubjectAltName],
+    unsupported: usize,
+    unsupported_treatment: &str,
+) -> String {
+    match unsupported {
+        0 => format_list(names),
+        unsupported => names
+            .iter()
+            .map(|name| name.to_string())
+            .chain(std::iter::once(format!(
+                "{} of an unsupported type, {}",
+                unsupported, unsupported_treatment
+            )))
+            .collect::<Vec<String>>()
+            .join(", "),
+    }
+}
+
+pub fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+pub fn format_list<T: ToString>(items: &[T]) -> String {
+    match items.is_empty() {
+        true => "none".to_string(),
+        false => items
+            .iter()
+            .map(|item| item.to_string())
+            .collect::<Vec<String>>()
+            .join(", "),
+    }
+}
+
+fn format_subtrees(is_ca: bool, subtrees: &[Subtree]) -> String {
+    match (is_ca, subtrees.is_empty()) {
+        (false, _) => "not applicable".to_string(),
+        (true, true) => "none".to_string(),
+        (true, false) => subtrees
+            .iter()
+            .map(|subtree| subtree.to_string())
+            .collect::<Vec<String>>()
+            .join(", "),
+    }
+}
+
+/// Work out the validity period. Durations are measured from --not-before when it is given and
+/// otherwise from now, while the validity period itself starts --backdate before now by default.
+pub fn resolve_validity(clap_matches: &ArgMatches) -> Result<crypto::Validity, Error> {
+    let now = Utc::now();
+
+    let (not_before, start) = match clap_matches.value_of("not-before") {
+        Some(raw_not_before) => {
+            let not_before = resolve_timestamp(raw_not_before)?;
+            (not_before, not_before)
+        }
+        None => (
+            now - resolve_duration(clap_matches.value_of("backdate").unwrap())?,
+            now,
+        ),
+    };
+
+    let after_start = |duration: Duration| {
+        start.checked_add_signed(duration).ok_or_else(|| {
+            Error::InvalidValidity("the validity period ends too far in the future".to_string())
+        })
+    };
+
+    let not_after = if clap_matches.is_present("no-well-defined-expiry") {
+        None
+    } else if let Some(raw_not_after) = clap_matches.value_of("not-after") {
+        Some(resolve_timestamp(raw_not_after)?)
+    } else if let Some(raw_valid_for) = clap_matches.value_of("valid-for") {
+        Some(after_start(resolve_duration(raw_valid_for)?)?)
+    } else {
+        let days_valid: u32 = clap_matches
+            .value_of_t("days-valid")
+            .unwrap_or_else(|err| err.exit());
+        Some(after_start(Duration::days(i64::from(days_valid)))?)
+    };
+
+    if let Some(not_after) = not_after {
+        if not_after <= not_before {
+            return Err(Error::InvalidValidity(format!(
+                "it ends at {}, which is not after its start at {}",
+                format_timestamp(not_after),
+                format_timestamp(not_before)
+            )));
+        }
+
+        if not_after.year() > 9999 {
+            return Err(Error::InvalidValidity(
+                "it must end before the year 10000, use --no-well-defined-expiry instead"
+                    .to_string(),
+            ));
+        }
+    }
+
+    Ok(crypto::Validity {
+        not_before,
+        not_after,
+    })
+}
+
+/// Make sure the certificate does not outlive its signer, either by refusing to create it or, when
+/// clamping was requested, by ending its validity period with the signer's. Returns whether the
+/// validity period was shortened.
+pub fn clamp_to_signer(
+    validity: &mut crypto::Validity,
+    signer_not_after: DateTime<Utc>,
+    clamp: bool,
+) -> Result<bool, Error> {
+    // Signer certificates without a well-defined expiration date end in the year 9999 too.
+    let exceeds_signer = match validity.not_after {
+        Some(not_after) => not_after > signer_not_after,
+        None => signer_not_after.year() < 9999,
+    };
+
+    if !exceeds_signer {
+        return Ok(false);
+    }
+
+    if !clamp {
+        return Err(Error::ValidityExceedsSigner {
+            not_after: validity
+                .not_after
+                .map(format_timestamp)
+                .unwrap_or_else(|| crypto::NO_WELL_DEFINED_EXPIRY.to_string()),
+            signer_not_after: format_timestamp(signer_not_after),
+        });
+    }
+
+    if signer_not_after <= validity.not_before {
+        return Err(Error::InvalidValidity(format!(
+            "the signer certificate expires at {}, before the validity period starts",
+            format_timestamp(signer_not_after)
+        )));
+    }
+
+    validity.not_after = Some(signer_not_after);
+    Ok(true)
+}
+
+fn resolve_timestamp(raw_timestamp: &str) -> Result<DateTime<Utc>, Error> {
+    DateTime::parse_from_rfc3339(raw_timestamp)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .map_err(|_| Error::InvalidTimestamp(raw_timestamp.to_string()))
+}
+
+/// Parse a duration such as `90s`, `12h` or `1h30m`.
+fn resolve_duration(raw_duration: &str) -> Result<Duration, Error> {
+    let invalid = || Error::InvalidDuration(raw_duration.to_string());
+    let mut seconds: i64 = 0;
+    let mut digits = String::new();
+
+    for c in raw_duration.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+
+        let unit_seconds = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            'w' => 7 * 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+
+        let value: i64 = digits.parse().map_err(|_| invalid())?;
+        seconds = value
+            .checked_mul(unit_seconds)
+            .and_then(|value_seconds| seconds.checked_add(value_seconds))
+            .filter(|seconds| *seconds <= Duration::max_value().num_seconds())
+            .ok_or_else(invalid)?;
+        digits.clear();
+    }
+
+    if raw_duration.is_empty() || !digits.is_empty() {
+        return Err(invalid());
+    }
+
+    Ok(Duration::seconds(seconds))
+}
+
+pub fn resolve_output_format(raw_output_format: Option<&str>) -> crypto::FileFormat {
     match raw_output_format {
         Some("der") => crypto::FileFormat::Der,
         Some("pem") => crypto::FileFormat::Pem,
@@ -227,10 +1075,544 @@ fn resolve_output_format(raw_output_format: Option<&str>) -> crypto::FileFormat
     }
 }
 
-fn resolve_key_type(result: Option<&str>) -> crypto::KeyType {
-    match result {
-        Some("ec") => crypto::KeyType::Ec,
-        Some("rsa") => crypto::KeyType::Rsa,
+/// Collect the requested subject alternative names, adding the common name as a DNS name when it
+/// looks like a hostname.
+pub fn resolve_subject_alt_names(
+    clap_matches: &ArgMatches,
+    common_name: &str,
+) -> Result<Vec<SubjectAltName>, Error> {
+    let mut subject_alt_names = Vec::new();
+
+    if let Some(name) = SubjectAltName::from_common_name(common_name) {
+        subject_alt_names.push(name);
+    }
+
+    let values = |arg| clap_matches.values_of(arg).into_iter().flatten();
+    let requested = values("dns")
+        .map(SubjectAltName::dns)
+        .chain(values("ip").map(SubjectAltName::ip))
+        .chain(values("email").map(SubjectAltName::email))
+        .chain(values("uri").map(SubjectAltName::uri));
+
+    for name in requested {
+        let name = name?;
+
+        if !subject_alt_names.contains(&name) {
+            subject_alt_names.push(name);
+        }
+    }
+
+    Ok(subject_alt_names)
+}
+
+/// Pick the requested subject alternative names to copy: all of them with
+/// --copy-subject-alt-names, or the ones given with --copy-subject-alt-name, which must be requested.
+fn resolve_copied_subject_alt_names(
+    clap_matches: &ArgMatches,
+    requested_subject_alt_names: &[SubjectAltName],
+) -> Result<Vec<SubjectAltName>, Error> {
+    if clap_matches.is_present("copy-subject-alt-names") {
+        return Ok(requested_subject_alt_names.to_vec());
+    }
+
+    clap_matches
+        .values_of("copy-subject-alt-name")
+        .into_iter()
+        .flatten()
+        .map(|raw_name| {
+            let name = SubjectAltName::parse(raw_name)?;
+
+            match requested_subject_alt_names.contains(&name) {
+                true => Ok(name),
+                false => Err(Error::InvalidSubjectAltName(format!(
+                    "\"{}\" is not requested",
+                    raw_name
+                ))),
+            }
+        })
+        .collect()
+}
+
+/// Collect the custom extensions: the requested ones chosen with --copy-extension, overridden by
+/// the ones given with --extension for the same OID.
+pub fn resolve_custom_extensions(
+    clap_matches: &ArgMatches,
+    requested_extensions: &[CustomExtension],
+) -> Result<Vec<CustomExtension>, Error> {
+    let given_extensions = clap_matches
+        .values_of("extension")
+        .into_iter()
+        .flatten()
+        .map(CustomExtension::parse)
+        .collect::<Result<Vec<CustomExtension>, Error>>()?;
+
+    // RFC 5280 allows each extension only once per certificate.
+    for (index, extension) in given_extensions.iter().enumerate() {
+        if given_extensions[..index]
+            .iter()
+            .any(|given_extension| given_extension.oid == extension.oid)
+        {
+            return Err(Error::InvalidExtension(format!(
+                "\"{}\" is given more than once",
+                extension.oid
+            )));
+        }
+    }
+
+    let mut custom_extensions = Vec::new();
+
+    for oid in clap_matches
+        .values_of("copy-extension")
+        .into_iter()
+        .flatten()
+    {
+        let requested_extension = requested_extensions
+            .iter()
+            .find(|extension| extension.oid == oid)
+            .ok_or_else(|| {
+                Error::InvalidExtension(format!(
+                    "\"{}\" is not requested by the certificate signing request",
+                    oid
+                ))
+            })?;
+
+        if requested_extension.is_managed() {
+            return Err(Error::InvalidExtension(format!(
+                "\"{}\" is an extension tch sets itself, use --copy-subject-alt-names or the dedicated options instead",
+                oid
+            )));
+        }
+
+        if !given_extensions
+            .iter()
+            .chain(custom_extensions.iter())
+            .any(|extension| extension.oid == oid)
+        {
+            custom_extensions.push(requested_extension.clone());
+        }
+    }
+
+    custom_extensions.extend(given_extensions);
+
+    Ok(custom_extensions)
+}
+
+/// Collect the requested certificate policies, merging the CPS URIs given for the same policy.
+fn resolve_certificate_policies(
+    clap_matches: &ArgMatches,
+) -> Result<Vec<CertificatePolicy>, Error> {
+    let mut policies: Vec<CertificatePolicy> = Vec::new();
+
+    for raw_policy in clap_matches.values_of("policy").into_iter().flatten() {
+        let policy = CertificatePolicy::parse(raw_policy)?;
+
+        match policies
+            .iter_mut()
+            .find(|existing| existing.oid == policy.oid)
+        {
+            Some(existing) => existing.cps_uris.extend(policy.cps_uris),
+            None => policies.push(policy),
+        }
+    }
+
+    Ok(policies)
+}
+
+/// The issuer URLs given with the options of the given prefix. URLs of a kind given on the command
+/// line replace the defaults of that kind, e.g. the ones from the signer's issuer URL file.
+pub fn resolve_issuer_urls(
+    clap_matches: &ArgMatches,
+    prefix: &str,
+    defaults: &IssuerUrls,
+) -> Result<IssuerUrls, Error> {
+    let urls = |arg: &str, default_urls: &Vec<String>| -> Result<Vec<String>, Error> {
+        let urls = clap_matches
+            .values_of(&*format!("{}{}", prefix, arg))
+            .into_iter()
+            .flatten()
+            .map(IssuerUrls::parse_url)
+            .collect::<Result<Vec<String>, Error>>()?;
+
+        match urls.is_empty() {
+            true => Ok(default_urls.clone()),
+            false => Ok(urls),
+        }
+    };
+
+    Ok(IssuerUrls {
+        ca_issuers: urls("ca-issuers-url", &defaults.ca_issuers)?,
+        crl_distribution_points: urls("crl-distribution-point", &defaults.crl_distribution_points)?,
+        ocsp: urls("ocsp-url", &defaults.ocsp)?,
+    })
+}
+
+/// The signer's issuer URL file and its URLs, which are empty when it has none.
+pub fn load_signer_issuer_urls(
+    signer_certificate_path: &Path,
+) -> Result<(Option<PathBuf>, IssuerUrls), Error> {
+    let issuer_urls_path = IssuerUrls::file_path(signer_certificate_path);
+
+    match IssuerUrls::load(&issuer_urls_path)? {
+        Some(issuer_urls) => Ok((Some(issuer_urls_path), issuer_urls)),
+        None => Ok((None, IssuerUrls::default())),
+    }
+}
+
+fn format_issued_urls(is_ca: bool, urls: &[String]) -> String {
+    match is_ca {
+        true => format_list(urls),
+        false => "not applicable".to_string(),
+    }
+}
+
+fn resolve_name_constraints(clap_matches: &ArgMatches) -> Result<NameConstraints, Error> {
+    let values = |arg| clap_matches.values_of(arg).into_iter().flatten();
+    let subtrees = |dns_arg, ip_arg, email_arg| -> Result<Vec<Subtree>, Error> {
+        values(dns_arg)
+            .map(Subtree::dns)
+            .chain(values(ip_arg).map(Subtree::ip))
+            .chain(values(email_arg).map(Subtree::email))
+            .collect()
+    };
+
+    Ok(NameConstraints {
+        permitted: subtrees("permitted-dns", "permitted-ip", "permitted-email")?,
+        excluded: subtrees("excluded-dns", "excluded-ip", "excluded-email")?,
+    })
+}
+
+/// The path length given with --path-length, or else the one of the cross-signed certificate. Roots
+/// are unconstrained by default and intermediates may only sign end-entity certificates.
+fn resolve_path_length(
+    path_length: Option<u32>,
+    cross_signed_certificate: Option<&X509>,
+    self_signed: bool,
+) -> Option<u32> {
+    match (path_length, cross_signed_certificate, self_signed) {
+        (Some(path_length), _, _) => Some(path_length),
+        (None, Some(certificate), _) => crypto::path_length(certificate),
+        (None, None, true) => None,
+        (None, None, false) => Some(0),
+    }
+}
+
+pub fn resolve_profile(raw_profile: Option<&str>) -> Profile {
+    match raw_profile {
+        // CA certificates have no profile, their key usage is fixed.
+        None => Profile::Mtls,
+        Some("client") => Profile::Client,
+        Some("code-signing") => Profile::CodeSigning,
+        Some("email") => Profile::Email,
+        Some("mtls") => Profile::Mtls,
+        Some("ocsp-signing") => Profile::OcspSigning,
+        Some("server") => Profile::Server,
+        Some("time-stamping") => Profile::TimeStamping,
+        _ => panic!("invalid raw profile"),
+    }
+}
+
+fn resolve_name_encoding(raw_name_encoding: Option<&str>) -> StringEncoding {
+    match raw_name_encoding {
+        Some("printable") => StringEncoding::Printable,
+        Some("utf8") => StringEncoding::Utf8,
+        _ => panic!("invalid raw name encoding"),
+    }
+}
+
+pub fn resolve_key_encoding(raw_key_encoding: Option<&str>) -> crypto::KeyEncoding {
+    match raw_key_encoding {
+        // Certificates signed for a certificate signing request write no private key.
+        None => crypto::KeyEncoding::Pkcs8,
+        Some("pkcs8") => crypto::KeyEncoding::Pkcs8,
+        Some("traditional") => crypto::KeyEncoding::Traditional,
+        _ => panic!("invalid raw key encoding"),
+    }
+}
+
+pub fn resolve_signature_hash(raw_signature_hash: Option<&str>) -> Option<crypto::HashAlgorithm> {
+    match raw_signature_hash {
+        Some("sha256") => Some(crypto::HashAlgorithm::Sha256),
+        Some("sha384") => Some(crypto::HashAlgorithm::Sha384),
+        Some("sha512") => Some(crypto::HashAlgorithm::Sha512),
+        None => None,
+        _ => panic!("invalid raw signature hash"),
+    }
+}
+
+pub fn resolve_signature_scheme(raw_signature_scheme: Option<&str>) -> crypto::SignatureScheme {
+    match raw_signature_scheme {
+        Some("pkcs1v15") => crypto::SignatureScheme::Pkcs1v15,
+        Some("pss") => crypto::SignatureScheme::Pss,
+        _ => panic!("invalid raw signature scheme"),
+    }
+}
+
+pub fn resolve_key_type(clap_matches: &ArgMatches) -> Result<crypto::KeyType, Error> {
+    match clap_matches.value_of("key-type") {
+        Some("ec") => Ok(crypto::KeyType::Ec(resolve_curve(
+            clap_matches.value_of("curve"),
+        ))),
+        Some("ed25519") => Ok(crypto::KeyType::Ed25519),
+        Some("ed448") => Ok(crypto::KeyType::Ed448),
+        Some("rsa") => Ok(crypto::KeyType::Rsa(resolve_rsa_parameters(clap_matches)?)),
+        Some("rsa-pss") => Ok(crypto::KeyType::RsaPss(resolve_rsa_parameters(
+            clap_matches,
+        )?)),
         _ => panic!("invalid raw key type"),
     }
 }
+
+fn resolve_rsa_parameters(clap_matches: &ArgMatches) -> Result<crypto::RsaParameters, Error> {
+    let key_size: u32 = clap_matches
+        .value_of_t("key-size")
+        .unwrap_or_else(|err| err.exit());
+    let public_exponent: u32 = clap_matches
+        .value_of_t("public-exponent")
+        .unwrap_or_else(|err| err.exit());
+
+    let rsa_parameters = crypto::RsaParameters {
+        key_size,
+        public_exponent,
+    };
+    check_rsa_parameters(&rsa_parameters)?;
+
+    Ok(rsa_parameters)
+}
+
+/// Refuse RSA parameters tch does not create keys with.
+pub fn check_rsa_parameters(rsa_parameters: &crypto::RsaParameters) -> Result<(), Error> {
+    if !(crypto::MIN_RSA_KEY_SIZE..=crypto::MAX_RSA_KEY_SIZE).contains(&rsa_parameters.key_size) {
+        return Err(Error::InvalidKeySize(rsa_parameters.key_size));
+    }
+
+    if rsa_parameters.public_exponent < 3 || rsa_parameters.public_exponent & 1 == 0 {
+        return Err(Error::InvalidPublicExponent(rsa_parameters.public_exponent));
+    }
+
+    Ok(())
+}
+
+fn resolve_curve(raw_curve: Option<&str>) -> crypto::EcCurve {
+    match raw_curve {
+        Some("p256") => crypto::EcCurve::P256,
+        Some("p384") => crypto::EcCurve::P384,
+        Some("p521") => crypto::EcCurve::P521,
+        Some("secp256k1") => crypto::EcCurve::Secp256k1,
+        _ => panic!("invalid raw curve"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use openssl::x509::extension::BasicConstraints;
+
+    fn seconds(raw_duration: &str) -> i64 {
+        resolve_duration(raw_duration).unwrap().num_seconds()
+    }
+
+    #[test]
+    fn resolve_path_length_gives_intermediates_0_by_default() {
+        assert_eq!(resolve_path_length(None, None, false), Some(0));
+        assert_eq!(resolve_path_length(Some(2), None, false), Some(2));
+    }
+
+    #[test]
+    fn resolve_path_length_leaves_roots_unconstrained_by_default() {
+        assert_eq!(resolve_path_length(None, None, true), None);
+        assert_eq!(resolve_path_length(Some(1), None, true), Some(1));
+    }
+
+    #[test]
+    fn resolve_path_length_copies_the_cross_signed_certificates() {
+        let mut basic_constraints = BasicConstraints::new();
+        basic_constraints.critical().ca().pathlen(3);
+
+        let mut certificate = X509::builder().unwrap();
+        certificate
+            .append_extension(basic_constraints.build().unwrap())
+            .unwrap();
+        let certificate = certificate.build();
+
+        assert_eq!(
+            resolve_path_length(None, Some(&certificate), false),
+            Some(3)
+        );
+        assert_eq!(
+            resolve_path_length(Some(1), Some(&certificate), false),
+            Some(1)
+        );
+    }
+
+    fn validity(not_after: Option<DateTime<Utc>>) -> crypto::Validity {
+        crypto::Validity {
+            not_before: Utc.ymd(2021, 1, 1).and_hms(0, 0, 0),
+            not_after,
+        }
+    }
+
+    fn resolve_validity_from(args: &[&str]) -> Result<crypto::Validity, Error> {
+        let clap_matches = clap::App::new("tch")
+            .arg(
+                clap::Arg::new("not-before")
+                    .long("not-before")
+                    .takes_value(true),
+            )
+            .arg(
+                clap::Arg::new("backdate")
+                    .long("backdate")
+                    .takes_value(true)
+                    .default_value("5m"),
+            )
+            .arg(
+                clap::Arg::new("days-valid")
+                    .long("days-valid")
+                    .takes_value(true),
+            )
+            .arg(
+                clap::Arg::new("valid-for")
+                    .long("valid-for")
+                    .takes_value(true),
+            )
+            .arg(
+                clap::Arg::new("not-after")
+                    .long("not-after")
+                    .takes_value(true),
+            )
+            .arg(clap::Arg::new("no-well-defined-expiry").long("no-well-defined-expiry"))
+            .get_matches_from(std::iter::once("tch").chain(args.iter().copied()));
+
+        resolve_validity(&clap_matches)
+    }
+
+    #[test]
+    fn resolve_validity_leaves_no_well_defined_expiry_open_until_clamped() {
+        let mut validity = resolve_validity_from(&[
+            "--not-before",
+            "2021-01-01T00:00:00Z",
+            "--no-well-defined-expiry",
+        ])
+        .unwrap();
+        let signer_not_after = Utc.ymd(2022, 1, 1).and_hms(0, 0, 0);
+
+        assert_eq!(validity.not_after, None);
+        assert!(matches!(
+            clamp_to_signer(&mut validity, signer_not_after, false),
+            Err(Error::ValidityExceedsSigner { .. })
+        ));
+        assert!(clamp_to_signer(&mut validity, signer_not_after, true).unwrap());
+        assert_eq!(validity.not_after, Some(signer_not_after));
+    }
+
+    #[test]
+    fn resolve_validity_refuses_a_period_ending_before_it_starts() {
+        assert!(matches!(
+            resolve_validity_from(&[
+                "--not-before",
+                "2021-01-01T00:00:00Z",
+                "--not-after",
+                "2021-01-01T00:00:00Z",
+            ]),
+            Err(Error::InvalidValidity(_))
+        ));
+        assert!(resolve_validity_from(&[
+            "--not-before",
+            "2021-01-01T00:00:00Z",
+            "--valid-for",
+            "1h",
+        ])
+        .is_ok());
+    }
+
+    #[test]
+    fn clamp_to_signer_keeps_a_period_within_the_signers() {
+        let signer_not_after = Utc.ymd(2022, 1, 1).and_hms(0, 0, 0);
+        let mut within = validity(Some(signer_not_after));
+
+        assert!(!clamp_to_signer(&mut within, signer_not_after, false).unwrap());
+        assert!(!clamp_to_signer(&mut within, signer_not_after, true).unwrap());
+        assert_eq!(within.not_after, Some(signer_not_after));
+    }
+
+    #[test]
+    fn clamp_to_signer_refuses_or_shortens_a_period_outliving_the_signer() {
+        let signer_not_after = Utc.ymd(2022, 1, 1).and_hms(0, 0, 0);
+        let mut outliving = validity(Some(Utc.ymd(2022, 1, 1).and_hms(0, 0, 1)));
+
+        assert!(matches!(
+            clamp_to_signer(&mut outliving, signer_not_after, false),
+            Err(Error::ValidityExceedsSigner { .. })
+        ));
+        assert!(clamp_to_signer(&mut outliving, signer_not_after, true).unwrap());
+        assert_eq!(outliving.not_after, Some(signer_not_after));
+    }
+
+    #[test]
+    fn clamp_to_signer_treats_no_well_defined_expiry_as_outliving_a_finite_signer() {
+        let signer_not_after = Utc.ymd(2022, 1, 1).and_hms(0, 0, 0);
+        let mut no_expiry = validity(None);
+
+        match clamp_to_signer(&mut no_expiry, signer_not_after, false) {
+            Err(Error::ValidityExceedsSigner { not_after, .. }) => {
+                assert_eq!(not_after, crypto::NO_WELL_DEFINED_EXPIRY)
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert!(clamp_to_signer(&mut no_expiry, signer_not_after, true).unwrap());
+        assert_eq!(no_expiry.not_after, Some(signer_not_after));
+    }
+
+    #[test]
+    fn clamp_to_signer_accepts_no_well_defined_expiry_below_a_signer_without_one() {
+        let signer_not_after = Utc.ymd(9999, 12, 31).and_hms(23, 59, 59);
+        let mut no_expiry = validity(None);
+
+        assert!(!clamp_to_signer(&mut no_expiry, signer_not_after, false).unwrap());
+        assert_eq!(no_expiry.not_after, None);
+    }
+
+    #[test]
+    fn clamp_to_signer_refuses_a_signer_expiring_before_the_period_starts() {
+        let signer_not_after = Utc.ymd(2020, 12, 31).and_hms(0, 0, 0);
+        let mut validity = validity(Some(Utc.ymd(2022, 1, 1).and_hms(0, 0, 0)));
+
+        assert!(matches!(
+            clamp_to_signer(&mut validity, signer_not_after, true),
+            Err(Error::InvalidValidity(_))
+        ));
+    }
+
+    #[test]
+    fn resolve_duration_accepts_every_unit() {
+        assert_eq!(seconds("90s"), 90);
+        assert_eq!(seconds("5m"), 5 * 60);
+        assert_eq!(seconds("12h"), 12 * 60 * 60);
+        assert_eq!(seconds("90d"), 90 * 24 * 60 * 60);
+        assert_eq!(seconds("2w"), 14 * 24 * 60 * 60);
+    }
+
+    #[test]
+    fn resolve_duration_adds_up_combined_units() {
+        assert_eq!(seconds("1h30m"), 90 * 60);
+        assert_eq!(seconds("1d1s"), 24 * 60 * 60 + 1);
+        assert_eq!(seconds("30m1h"), 90 * 60);
+        assert_eq!(seconds("0s"), 0);
+    }
+
+    #[test]
+    fn resolve_duration_rejects_malformed_durations() {
+        for raw_duration in &[
+            "", "h", "12", "1h30", "1y", "1.5h", "-1h", " 1h", "1 h", "1hh",
+        ] {
+            assert!(resolve_duration(raw_duration).is_err(), "{}", raw_duration);
+        }
+    }
+
+    #[test]
+    fn resolve_duration_rejects_overflowing_durations() {
+        assert!(resolve_duration("99999999999999999999s").is_err());
+        assert!(resolve_duration("9223372036854775807w").is_err());
+        assert!(resolve_duration("9223372036854776s").is_err());
+    }
+}
@@ -1,2 +1,6 @@
 pub mod create_ca_certificate;
 pub mod create_certificate;
+pub mod create_csr;
+pub mod cross_sign;
+pub mod renew;
+pub mod sign_csr;
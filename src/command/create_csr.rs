@@ -0,0 +1,7 @@
+use crate::csr;
+use crate::Error;
+use clap::ArgMatches;
+
+pub fn run(clap_matches: &ArgMatches) -> Result<(), Error> {
+    csr::run(clap_matches)
+}
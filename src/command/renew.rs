@@ -0,0 +1,7 @@
+use crate::renewal;
+use crate::Error;
+use clap::ArgMatches;
+
+pub fn run(clap_matches: &ArgMatches) -> Result<(), Error> {
+    renewal::run(clap_matches)
+}
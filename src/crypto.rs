@@ -1,19 +1,36 @@
+pub mod extensions;
+mod ffi;
+pub mod issuer_urls;
+pub mod name;
+pub mod name_constraints;
+pub mod profile;
+pub mod request;
+pub mod san;
+
+use crate::Error;
+use chrono::{DateTime, TimeZone, Utc};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use strum_macros::Display;
 
-use openssl::asn1::Asn1Time;
+use openssl::asn1::{Asn1Integer, Asn1Time, Asn1TimeRef};
+use openssl::base64;
 use openssl::bn::{BigNum, MsbOption};
 use openssl::ec::*;
 use openssl::error::ErrorStack;
 use openssl::hash::MessageDigest;
-use openssl::pkey::{PKey, Private};
+use openssl::nid::Nid;
+use openssl::pkey::{HasPublic, Id, PKey, PKeyRef, Private, Public};
 use openssl::rsa::Rsa;
+use openssl::symm::Cipher;
 
+use openssl::stack::Stack;
 use openssl::x509::extension::{
     AuthorityKeyIdentifier, BasicConstraints, KeyUsage, SubjectKeyIdentifier,
 };
-use openssl::x509::{X509NameBuilder, X509Req, X509ReqBuilder, X509};
+use openssl::x509::{
+    X509Builder, X509Extension, X509ExtensionRef, X509NameRef, X509Req, X509ReqBuilder, X509,
+};
 
 #[derive(Display, Debug)]
 pub enum FileFormat {
@@ -23,54 +40,382 @@ pub enum FileFormat {
 
 #[derive(Display, Debug)]
 pub enum KeyType {
-    Ec,
-    Rsa,
+    Ec(EcCurve),
+    Ed25519,
+    Ed448,
+    Rsa(RsaParameters),
+    #[strum(to_string = "rsa-pss")]
+    RsaPss(RsaParameters),
+}
+
+/// The structure private keys are encoded in, independent of the PEM or DER file format.
+#[derive(Display, Debug)]
+pub enum KeyEncoding {
+    /// PKCS#8 PrivateKeyInfo, or EncryptedPrivateKeyInfo when encrypted.
+    #[strum(to_string = "pkcs8")]
+    Pkcs8,
+    /// The key type specific structure: SEC1 ECPrivateKey or PKCS#1 RSAPrivateKey.
+    #[strum(to_string = "traditional")]
+    Traditional,
+}
+
+#[derive(Display, Debug, Clone, Copy)]
+pub enum HashAlgorithm {
+    #[strum(to_string = "sha256")]
+    Sha256,
+    #[strum(to_string = "sha384")]
+    Sha384,
+    #[strum(to_string = "sha512")]
+    Sha512,
+}
+
+impl HashAlgorithm {
+    fn message_digest(self) -> MessageDigest {
+        match self {
+            HashAlgorithm::Sha256 => MessageDigest::sha256(),
+            HashAlgorithm::Sha384 => MessageDigest::sha384(),
+            HashAlgorithm::Sha512 => MessageDigest::sha512(),
+        }
+    }
+}
+
+#[derive(Display, Debug)]
+pub enum SignatureScheme {
+    #[strum(to_string = "pkcs1v15")]
+    Pkcs1v15,
+    #[strum(to_string = "pss")]
+    Pss,
+}
+
+#[derive(Display, Debug, Clone, Copy)]
+pub enum EcCurve {
+    #[strum(to_string = "p256")]
+    P256,
+    #[strum(to_string = "p384")]
+    P384,
+    #[strum(to_string = "p521")]
+    P521,
+    #[strum(to_string = "secp256k1")]
+    Secp256k1,
+}
+
+impl KeyType {
+    /// Whether keys of this type have a traditional, key type specific encoding.
+    pub fn has_traditional_encoding(&self) -> bool {
+        matches!(self, KeyType::Ec(_) | KeyType::Rsa(_))
+    }
+}
+
+impl EcCurve {
+    fn nid(self) -> Nid {
+        match self {
+            EcCurve::P256 => Nid::X9_62_PRIME256V1,
+            EcCurve::P384 => Nid::SECP384R1,
+            EcCurve::P521 => Nid::SECP521R1,
+            EcCurve::Secp256k1 => Nid::SECP256K1,
+        }
+    }
+}
+
+/// The type and parameters of an existing key, e.g. so that a renewed certificate can be rekeyed to
+/// a key like the one it replaces. None for key types tch cannot generate.
+pub fn key_type_of<T: HasPublic>(key: &PKeyRef<T>) -> Option<KeyType> {
+    let rsa_parameters = || {
+        let rsa = key.rsa().ok()?;
+        Some(RsaParameters {
+            key_size: rsa.size() * 8,
+            public_exponent: rsa.e().to_dec_str().ok()?.parse().ok()?,
+        })
+    };
+
+    match key.id() {
+        Id::EC => {
+            let curve = match key.ec_key().ok()?.group().curve_name()? {
+                Nid::X9_62_PRIME256V1 => EcCurve::P256,
+                Nid::SECP384R1 => EcCurve::P384,
+                Nid::SECP521R1 => EcCurve::P521,
+                Nid::SECP256K1 => EcCurve::Secp256k1,
+                _ => return None,
+            };
+            Some(KeyType::Ec(curve))
+        }
+        Id::ED25519 => Some(KeyType::Ed25519),
+        Id::ED448 => Some(KeyType::Ed448),
+        Id::RSA => Some(KeyType::Rsa(rsa_parameters()?)),
+        _ if ffi::is_rsa_pss_key(key) => Some(KeyType::RsaPss(rsa_parameters()?)),
+        _ => None,
+    }
+}
+
+/// RSA keys smaller than this are rejected.
+pub const MIN_RSA_KEY_SIZE: u32 = 2048;
+
+/// RSA keys larger than this are rejected, generating them takes too long to be useful.
+pub const MAX_RSA_KEY_SIZE: u32 = 16384;
+
+#[derive(Debug)]
+pub struct RsaParameters {
+    pub key_size: u32,
+    pub public_exponent: u32,
+}
+
+/// The OIDs of the extensions tch sets itself.
+pub const AUTHORITY_INFO_ACCESS_OID: &str = "1.3.6.1.5.5.7.1.1";
+pub const AUTHORITY_KEY_IDENTIFIER_OID: &str = "2.5.29.35";
+pub const BASIC_CONSTRAINTS_OID: &str = "2.5.29.19";
+pub const CERTIFICATE_POLICIES_OID: &str = "2.5.29.32";
+pub const CRL_DISTRIBUTION_POINTS_OID: &str = "2.5.29.31";
+pub const EXTENDED_KEY_USAGE_OID: &str = "2.5.29.37";
+pub const KEY_USAGE_OID: &str = "2.5.29.15";
+pub const NAME_CONSTRAINTS_OID: &str = "2.5.29.30";
+pub const SUBJECT_ALT_NAME_OID: &str = "2.5.29.17";
+pub const SUBJECT_KEY_IDENTIFIER_OID: &str = "2.5.29.14";
+
+/// The GeneralizedTime RFC 5280 assigns to certificates that have no well-defined expiration date.
+pub const NO_WELL_DEFINED_EXPIRY: &str = "99991231235959Z";
+
+/// The validity period of a created certificate.
+#[derive(Debug, Clone, Copy)]
+pub struct Validity {
+    pub not_before: DateTime<Utc>,
+    /// None when the certificate has no well-defined expiration date.
+    pub not_after: Option<DateTime<Utc>>,
+}
+
+impl Validity {
+    fn set(&self, cert_builder: &mut X509Builder) -> Result<(), ErrorStack> {
+        let not_before = Asn1Time::from_unix(self.not_before.timestamp() as _)?;
+        cert_builder.set_not_before(&not_before)?;
+
+        let not_after = match self.not_after {
+            Some(not_after) => Asn1Time::from_unix(not_after.timestamp() as _)?,
+            None => Asn1Time::from_str_x509(NO_WELL_DEFINED_EXPIRY)?,
+        };
+        cert_builder.set_not_after(&not_after)
+    }
+}
+
+/// The key a certificate is created for.
+pub enum SubjectKey {
+    /// A new key pair of the given type.
+    Generate(KeyType),
+    /// The existing private key at the given path, which is never written back out.
+    PrivateKey(PathBuf),
+    /// Only the public key, e.g. of a certificate signing request, whose private key tch never
+    /// sees.
+    PublicKey(PKey<Public>),
+}
+
+/// The encoded files making up a created credential.
+pub struct Credentials {
+    pub certificate: Vec<u8>,
+    /// Absent unless a new key pair was generated.
+    pub private_key: Option<Vec<u8>>,
+    /// Present when public key export was requested.
+    pub public_key: Option<Vec<u8>>,
 }
 
 pub struct Config {
-    pub common_name: String,
-    pub days_valid: u32,
-    pub key_type: KeyType,
+    pub certificate_policies: Vec<extensions::CertificatePolicy>,
+    pub custom_extensions: Vec<extensions::CustomExtension>,
+    /// The passphrase of an encrypted existing private key.
+    pub existing_private_key_passphrase: Option<String>,
+    pub export_public_key: bool,
+    /// The signer's revocation and certificate URLs to embed in the created certificate.
+    pub issuer_urls: issuer_urls::IssuerUrls,
+    pub key_encoding: KeyEncoding,
+    /// The subtrees CA certificates restrict the names of the certificates below them to. A
+    /// cross-signed certificate copies the ones of the certificate it is made for when empty.
+    pub name_constraints: name_constraints::NameConstraints,
     pub output_format: FileFormat,
+    /// The maximum number of CA certificates that may follow a CA certificate in a path.
+    pub path_length: Option<u32>,
+    pub private_key_passphrase: Option<String>,
+    /// The intended use of end-entity certificates.
+    pub profile: profile::Profile,
+    pub signature_hash: Option<HashAlgorithm>,
+    pub signature_scheme: SignatureScheme,
     pub signer_certificate_path: Option<PathBuf>,
+    pub signer_private_key_passphrase: Option<String>,
     pub signer_private_key_path: Option<PathBuf>,
+    /// None when the subject of an existing certificate is copied as it is.
+    pub subject: Option<name::DistinguishedName>,
+    pub subject_alt_names: Vec<san::SubjectAltName>,
+    pub subject_key: SubjectKey,
     pub self_signed: bool,
+    pub validity: Validity,
 }
 
-/// Make a CA certificate and private key
-pub fn create_ca_certificate(config: &Config) -> Result<(Vec<u8>, Vec<u8>), ErrorStack> {
-    let private_key = match config.key_type {
-        KeyType::Ec => {
-            let ec_group = EcGroup::from_curve_name(openssl::nid::Nid::X9_62_PRIME256V1)?;
-            let ec = EcKey::generate(&ec_group)?;
-            PKey::from_ec_key(ec)?
-        }
-        KeyType::Rsa => {
-            let rsa = Rsa::generate(2048)?;
-            PKey::from_rsa(rsa)?
-        }
+/// The encoded files making up a created certificate signing request.
+pub struct RequestCredentials {
+    pub request: Vec<u8>,
+    /// Absent when the request was created for an existing private key.
+    pub private_key: Option<Vec<u8>>,
+    /// Present when public key export was requested.
+    pub public_key: Option<Vec<u8>>,
+}
+
+pub struct RequestConfig {
+    pub custom_extensions: Vec<extensions::CustomExtension>,
+    /// The passphrase of an encrypted existing private key.
+    pub existing_private_key_passphrase: Option<String>,
+    pub export_public_key: bool,
+    pub key_encoding: KeyEncoding,
+    pub key_type: KeyType,
+    pub output_format: FileFormat,
+    pub private_key_passphrase: Option<String>,
+    pub private_key_path: Option<PathBuf>,
+    /// The intended use to request key usage and extended key usage for, if any.
+    pub profile: Option<profile::Profile>,
+    pub signature_hash: Option<HashAlgorithm>,
+    pub subject: name::DistinguishedName,
+    pub subject_alt_names: Vec<san::SubjectAltName>,
+}
+
+/// Make a certificate signing request and private key
+pub fn create_certificate_request(config: &RequestConfig) -> Result<RequestCredentials, Error> {
+    let private_key = match &config.private_key_path {
+        Some(private_key_path) => load_private_key(
+            private_key_path,
+            config.existing_private_key_passphrase.as_deref(),
+        )?,
+        None => generate_private_key(&config.key_type)?,
     };
 
-    let mut x509_name = X509NameBuilder::new()?;
-    x509_name.append_entry_by_text("CN", &config.common_name)?;
-    let x509_name = x509_name.build();
+    let mut requested_extensions = Vec::new();
+
+    if let Some(profile) = config.profile {
+        requested_extensions.extend(profile.extensions(&private_key)?);
+    }
+
+    if !config.subject_alt_names.is_empty() {
+        let req_builder = X509ReqBuilder::new()?;
+        requested_extensions.push(san::to_extension(
+            &config.subject_alt_names,
+            &req_builder.x509v3_context(None),
+        )?);
+    }
+
+    for custom_extension in &config.custom_extensions {
+        requested_extensions.push(custom_extension.to_extension()?);
+    }
+
+    let request = mk_request(
+        &config.subject,
+        requested_extensions,
+        &private_key,
+        config.signature_hash,
+    )?;
+
+    let request_bytes = match config.output_format {
+        FileFormat::Pem => request.to_pem()?,
+        FileFormat::Der => request.to_der()?,
+    };
+
+    let public_key_bytes = match config.export_public_key {
+        true => Some(encode_public_key(&private_key, &config.output_format)?),
+        false => None,
+    };
+
+    let private_key_bytes = match config.private_key_path {
+        Some(_) => None,
+        None => Some(encode_private_key(
+            &private_key,
+            &config.key_encoding,
+            &config.output_format,
+            config.private_key_passphrase.as_deref(),
+        )?),
+    };
+
+    Ok(RequestCredentials {
+        request: request_bytes,
+        private_key: private_key_bytes,
+        public_key: public_key_bytes,
+    })
+}
+
+/// Make a CA certificate and private key
+pub fn create_ca_certificate(config: &Config) -> Result<Credentials, Error> {
+    let (private_key, public_key) = load_subject_key(
+        &config.subject_key,
+        config.existing_private_key_passphrase.as_deref(),
+    )?;
+
+    let x509_name = config
+        .subject
+        .as_ref()
+        .expect("created certificates are only made for a given subject")
+        .to_x509_name()?;
+    let certificate =
+        build_ca_certificate(config, &x509_name, &private_key, &public_key, None, None)?;
+
+    encode_output(&certificate, private_key.as_ref(), &public_key, config)
+}
+
+/// Make a CA certificate for the subject and public key of an existing CA certificate, signed by
+/// the configured signer. The subject name and SubjectKeyIdentifier are copied unchanged so that
+/// paths build through either certificate, and so are the NameConstraints unless others are given.
+pub fn cross_sign_certificate(config: &Config, certificate: &X509) -> Result<Credentials, Error> {
+    let public_key = certificate.public_key()?;
+
+    let subject_key_identifier = ffi::extensions(certificate)
+        .into_iter()
+        .find(|extension| ffi::extension_parts(extension).0 == SUBJECT_KEY_IDENTIFIER_OID);
+
+    let cross_certificate = build_ca_certificate(
+        config,
+        certificate.subject_name(),
+        &None,
+        &public_key,
+        subject_key_identifier,
+        copied_name_constraints(config, certificate),
+    )?;
+
+    encode_output(&cross_certificate, None, &public_key, config)
+}
+
+/// The NameConstraints extension a cross-signed certificate copies from the certificate it is made
+/// for, which is none when name constraints are given.
+pub fn copied_name_constraints<'a>(
+    config: &Config,
+    certificate: &'a X509,
+) -> Option<&'a X509ExtensionRef> {
+    match config.name_constraints.is_empty() {
+        true => ffi::extensions(certificate)
+            .into_iter()
+            .find(|extension| ffi::extension_parts(extension).0 == NAME_CONSTRAINTS_OID),
+        false => None,
+    }
+}
+
+/// Build and sign a CA certificate for the subject name and key. The SubjectKeyIdentifier is
+/// computed from the public key unless an existing one is given, and existing NameConstraints are
+/// used instead of the configured ones.
+fn build_ca_certificate(
+    config: &Config,
+    x509_name: &X509NameRef,
+    private_key: &Option<PKey<Private>>,
+    public_key: &PKey<Public>,
+    subject_key_identifier: Option<&X509ExtensionRef>,
+    name_constraints: Option<&X509ExtensionRef>,
+) -> Result<X509, Error> {
     let mut cert_builder = X509::builder()?;
     cert_builder.set_version(2)?;
 
-    let serial_number = {
-        let mut serial = BigNum::new()?;
-        serial.rand(159, MsbOption::MAYBE_ZERO, false)?;
-        serial.to_asn1_integer()?
-    };
+    let serial_number = random_serial_number()?;
 
     cert_builder.set_serial_number(&serial_number)?;
-    cert_builder.set_subject_name(&x509_name)?;
-    cert_builder.set_pubkey(&private_key)?;
-    let not_before = Asn1Time::days_from_now(0)?;
-    cert_builder.set_not_before(&not_before)?;
-    let not_after = Asn1Time::days_from_now(config.days_valid)?;
-    cert_builder.set_not_after(&not_after)?;
-    cert_builder.append_extension(BasicConstraints::new().critical().ca().build()?)?;
+    cert_builder.set_subject_name(x509_name)?;
+    cert_builder.set_pubkey(public_key)?;
+    config.validity.set(&mut cert_builder)?;
+    let mut basic_constraints = BasicConstraints::new();
+    basic_constraints.critical().ca();
+
+    if let Some(path_length) = config.path_length {
+        basic_constraints.pathlen(path_length);
+    }
+
+    cert_builder.append_extension(basic_constraints.build()?)?;
 
     cert_builder.append_extension(
         KeyUsage::new()
@@ -80,13 +425,36 @@ pub fn create_ca_certificate(config: &Config) -> Result<(Vec<u8>, Vec<u8>), Erro
             .build()?,
     )?;
 
-    let subject_key_identifier =
-        SubjectKeyIdentifier::new().build(&cert_builder.x509v3_context(None, None))?;
+    if let Some(name_constraints) = name_constraints {
+        cert_builder.append_extension2(name_constraints)?;
+    } else if !config.name_constraints.is_empty() {
+        let name_constraints = config
+            .name_constraints
+            .to_extension(&cert_builder.x509v3_context(None, None))?;
+        cert_builder.append_extension(name_constraints)?;
+    }
 
-    cert_builder.append_extension(subject_key_identifier)?;
+    for extension in config
+        .issuer_urls
+        .to_extensions(&cert_builder.x509v3_context(None, None))?
+    {
+        cert_builder.append_extension(extension)?;
+    }
 
-    if config.self_signed {
-        cert_builder.set_issuer_name(&x509_name)?;
+    append_policies_and_custom_extensions(&mut cert_builder, config)?;
+
+    match subject_key_identifier {
+        Some(subject_key_identifier) => cert_builder.append_extension2(subject_key_identifier)?,
+        None => {
+            let subject_key_identifier =
+                SubjectKeyIdentifier::new().build(&cert_builder.x509v3_context(None, None))?;
+
+            cert_builder.append_extension(subject_key_identifier)?;
+        }
+    }
+
+    let certificate = if config.self_signed {
+        cert_builder.set_issuer_name(x509_name)?;
 
         let auth_key_identifier = AuthorityKeyIdentifier::new()
             .keyid(false)
@@ -94,9 +462,10 @@ pub fn create_ca_certificate(config: &Config) -> Result<(Vec<u8>, Vec<u8>), Erro
             .build(&cert_builder.x509v3_context(None, None))?;
 
         cert_builder.append_extension(auth_key_identifier)?;
-        cert_builder.sign(&private_key, MessageDigest::sha384())?;
+        sign(cert_builder, self_signing_key(private_key), config)?
     } else {
-        let (signer_certificate, signer_private_key) = load_ca(config).unwrap();
+        let (signer_certificate, signer_private_key) = load_ca(config)?;
+        check_path_length(&signer_certificate, config.path_length)?;
         cert_builder.set_issuer_name(signer_certificate.subject_name())?;
 
         let auth_key_identifier = AuthorityKeyIdentifier::new()
@@ -105,122 +474,435 @@ pub fn create_ca_certificate(config: &Config) -> Result<(Vec<u8>, Vec<u8>), Erro
             .build(&cert_builder.x509v3_context(Some(&signer_certificate), None))?;
 
         cert_builder.append_extension(auth_key_identifier)?;
-        cert_builder.sign(&signer_private_key, MessageDigest::sha384())?;
+        sign(cert_builder, &signer_private_key, config)?
+    };
+
+    Ok(certificate)
+}
+
+/// Append the certificate policies and the custom extensions, which apply to CA and end-entity
+/// certificates alike.
+fn append_policies_and_custom_extensions(
+    cert_builder: &mut X509Builder,
+    config: &Config,
+) -> Result<(), ErrorStack> {
+    if !config.certificate_policies.is_empty() {
+        cert_builder.append_extension(extensions::certificate_policies_extension(
+            &config.certificate_policies,
+        )?)?;
     }
 
-    let certificate = cert_builder.build();
-    let certificate_bytes: Vec<u8>;
-    let private_key_bytes: Vec<u8>;
+    for custom_extension in &config.custom_extensions {
+        cert_builder.append_extension(custom_extension.to_extension()?)?;
+    }
 
-    match config.output_format {
-        FileFormat::Pem => {
-            certificate_bytes = certificate.to_pem().unwrap();
-            private_key_bytes = private_key.private_key_to_pem_pkcs8().unwrap();
-        }
-        FileFormat::Der => {
-            certificate_bytes = certificate.to_der().unwrap();
-            private_key_bytes = private_key.private_key_to_der().unwrap();
+    Ok(())
+}
+
+/// Refuse to create a CA certificate below a signer whose path length constraint leaves no room for
+/// it, or whose constraint would be loosened by the created certificate's own path length.
+fn check_path_length(signer_certificate: &X509, path_length: Option<u32>) -> Result<(), Error> {
+    match (ffi::path_length(signer_certificate), path_length) {
+        (Some(0), _) => Err(Error::SignerPathLengthExhausted),
+        (Some(signer_path_length), None) => Err(Error::PathLengthExceeded {
+            path_length: None,
+            signer_path_length,
+        }),
+        (Some(signer_path_length), Some(path_length)) if path_length >= signer_path_length => {
+            Err(Error::PathLengthExceeded {
+                path_length: Some(path_length),
+                signer_path_length,
+            })
         }
+        _ => Ok(()),
     }
+}
+
+/// Encode the certificate, the public key if requested and, if a new key pair was generated, the
+/// private key in the configured file format and key encoding.
+fn encode_output(
+    certificate: &X509,
+    private_key: Option<&PKey<Private>>,
+    public_key: &PKey<Public>,
+    config: &Config,
+) -> Result<Credentials, Error> {
+    let certificate_bytes = match config.output_format {
+        FileFormat::Pem => certificate.to_pem()?,
+        FileFormat::Der => certificate.to_der()?,
+    };
+
+    let public_key_bytes = match config.export_public_key {
+        true => Some(encode_public_key(public_key, &config.output_format)?),
+        false => None,
+    };
+
+    // An existing private key is never written back out.
+    let private_key_bytes = match (&config.subject_key, private_key) {
+        (SubjectKey::Generate(_), Some(private_key)) => Some(encode_private_key(
+            private_key,
+            &config.key_encoding,
+            &config.output_format,
+            config.private_key_passphrase.as_deref(),
+        )?),
+        _ => None,
+    };
 
-    Ok((certificate_bytes, private_key_bytes))
+    Ok(Credentials {
+        certificate: certificate_bytes,
+        private_key: private_key_bytes,
+        public_key: public_key_bytes,
+    })
 }
 
-fn load_ca(config: &Config) -> Result<(X509, PKey<Private>), ErrorStack> {
-    let cert_bytes: Vec<u8> = fs::read(&config.signer_certificate_path.as_ref().unwrap()).unwrap();
-    let pkey_bytes: Vec<u8> = fs::read(&config.signer_private_key_path.as_ref().unwrap()).unwrap();
+/// Encode the SubjectPublicKeyInfo of the key.
+fn encode_public_key<T: HasPublic>(
+    key: &PKeyRef<T>,
+    output_format: &FileFormat,
+) -> Result<Vec<u8>, ErrorStack> {
+    match output_format {
+        FileFormat::Pem => key.public_key_to_pem(),
+        FileFormat::Der => key.public_key_to_der(),
+    }
+}
 
-    let pkey: PKey<Private>;
+/// Encode the private key in the given file format and key encoding, encrypting it when a
+/// passphrase is given.
+fn encode_private_key(
+    private_key: &PKey<Private>,
+    key_encoding: &KeyEncoding,
+    output_format: &FileFormat,
+    passphrase: Option<&str>,
+) -> Result<Vec<u8>, Error> {
+    match key_encoding {
+        KeyEncoding::Pkcs8 => {
+            let private_key_pem = match passphrase {
+                Some(passphrase) => private_key.private_key_to_pem_pkcs8_passphrase(
+                    Cipher::aes_256_cbc(),
+                    passphrase.as_bytes(),
+                )?,
+                None => private_key.private_key_to_pem_pkcs8()?,
+            };
 
-    match config
-        .signer_private_key_path
-        .as_ref()
-        .unwrap()
-        .extension()
-        .unwrap()
-        .to_str()
-        .unwrap()
-    {
-        "der" => pkey = PKey::private_key_from_der(&pkey_bytes).unwrap(),
-        "pem" => pkey = PKey::private_key_from_pem(&pkey_bytes).unwrap(),
-        _ => panic!("invalid signer private key file extension"),
+            match output_format {
+                FileFormat::Pem => Ok(private_key_pem),
+                FileFormat::Der => Ok(pem_to_der(&private_key_pem)?),
+            }
+        }
+        KeyEncoding::Traditional => match (private_key.id(), output_format) {
+            (Id::EC, FileFormat::Pem) => Ok(private_key.ec_key()?.private_key_to_pem()?),
+            (Id::EC, FileFormat::Der) => Ok(private_key.ec_key()?.private_key_to_der()?),
+            (Id::RSA, FileFormat::Pem) => Ok(private_key.rsa()?.private_key_to_pem()?),
+            (Id::RSA, FileFormat::Der) => Ok(private_key.rsa()?.private_key_to_der()?),
+            _ => Err(Error::TraditionalKeyEncodingUnavailable),
+        },
     }
+}
 
-    let cert;
+/// Strip the armor of a PEM document, leaving the DER encoded structure within it.
+fn pem_to_der(pem: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+    let body: String = String::from_utf8_lossy(pem)
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .collect();
 
-    match config
-        .signer_certificate_path
-        .as_ref()
-        .unwrap()
-        .extension()
-        .unwrap()
-        .to_str()
-        .unwrap()
-    {
-        "der" => cert = X509::from_der(&cert_bytes).unwrap(),
-        "pem" => cert = X509::from_pem(&cert_bytes).unwrap(),
-        _ => panic!("invalid signer certificate file extension"),
+    base64::decode_block(&body)
+}
+
+/// Generate or load the subject's key. The private key is only available when tch holds it.
+fn load_subject_key(
+    subject_key: &SubjectKey,
+    passphrase: Option<&str>,
+) -> Result<(Option<PKey<Private>>, PKey<Public>), Error> {
+    let private_key = match subject_key {
+        SubjectKey::Generate(key_type) => generate_private_key(key_type)?,
+        SubjectKey::PrivateKey(private_key_path) => load_private_key(private_key_path, passphrase)?,
+        SubjectKey::PublicKey(public_key) => return Ok((None, public_key.clone())),
     };
 
-    Ok((cert, pkey))
+    let public_key = PKey::public_key_from_der(&private_key.public_key_to_der()?)?;
+
+    Ok((Some(private_key), public_key))
 }
 
-// /// Make a certificate and private key signed by the given CA cert and private key
-pub fn create_certificate(config: &Config) -> Result<(Vec<u8>, Vec<u8>), ErrorStack> {
-    let private_key = match config.key_type {
-        KeyType::Ec => {
-            let ec_group = EcGroup::from_curve_name(openssl::nid::Nid::X9_62_PRIME256V1)?;
+/// The key a self-signed certificate is signed with, which is its own private key.
+fn self_signing_key(private_key: &Option<PKey<Private>>) -> &PKeyRef<Private> {
+    private_key
+        .as_ref()
+        .expect("self-signed certificates are only created for a private key")
+}
+
+fn generate_private_key(key_type: &KeyType) -> Result<PKey<Private>, ErrorStack> {
+    match *key_type {
+        KeyType::Ec(curve) => {
+            let ec_group = EcGroup::from_curve_name(curve.nid())?;
             let ec = EcKey::generate(&ec_group)?;
-            PKey::from_ec_key(ec)?
+            PKey::from_ec_key(ec)
+        }
+        KeyType::Ed25519 => PKey::generate_ed25519(),
+        KeyType::Ed448 => PKey::generate_ed448(),
+        KeyType::Rsa(RsaParameters {
+            key_size,
+            public_exponent,
+        }) => {
+            let public_exponent = BigNum::from_u32(public_exponent)?;
+            let rsa = Rsa::generate_with_e(key_size, &public_exponent)?;
+            PKey::from_rsa(rsa)
         }
-        KeyType::Rsa => {
-            let rsa = Rsa::generate(2048)?;
-            PKey::from_rsa(rsa)?
+        KeyType::RsaPss(RsaParameters {
+            key_size,
+            public_exponent,
+        }) => {
+            let public_exponent = BigNum::from_u32(public_exponent)?;
+            let rsa = Rsa::generate_with_e(key_size, &public_exponent)?;
+            ffi::rsa_pss_key_from_rsa(rsa)
         }
+    }
+}
+
+/// Sign the certificate with the given signing key, honouring the configured signature scheme.
+///
+/// RSASSA-PSS keys always produce PSS signatures. Plain RSA keys produce PKCS#1 v1.5 signatures
+/// unless the PSS signature scheme was requested.
+fn sign(
+    mut cert_builder: X509Builder,
+    signing_key: &PKeyRef<Private>,
+    config: &Config,
+) -> Result<X509, ErrorStack> {
+    let digest = signature_digest(signing_key, config.signature_hash);
+
+    let use_pss_padding = ffi::is_rsa_pss_key(signing_key)
+        || matches!(
+            (&config.signature_scheme, signing_key.id()),
+            (SignatureScheme::Pss, Id::RSA)
+        );
+
+    match use_pss_padding {
+        true => {
+            let mut certificate = cert_builder.build();
+            ffi::sign_with_pss_padding(&mut certificate, signing_key, digest)?;
+            Ok(certificate)
+        }
+        false => {
+            cert_builder.sign(signing_key, digest)?;
+            Ok(cert_builder.build())
+        }
+    }
+}
+
+/// Pick the digest used when signing with the given key.
+///
+/// EdDSA signs the message itself rather than a digest of it, so OpenSSL must be handed the null
+/// digest whenever the signing key is Ed25519 or Ed448. Otherwise the configured hash is used or,
+/// when none was configured, one matching the strength of the signing key: SHA-256 for P-256,
+/// SHA-384 for P-384 and SHA-512 for P-521. RSA keys default to SHA-384.
+fn signature_digest(
+    signing_key: &PKeyRef<Private>,
+    signature_hash: Option<HashAlgorithm>,
+) -> MessageDigest {
+    let hash_algorithm = match (signing_key.id(), signature_hash) {
+        (Id::ED25519, _) | (Id::ED448, _) => return MessageDigest::null(),
+        (_, Some(hash_algorithm)) => hash_algorithm,
+        (Id::EC, None) if signing_key.bits() <= 256 => HashAlgorithm::Sha256,
+        (Id::EC, None) if signing_key.bits() <= 384 => HashAlgorithm::Sha384,
+        (Id::EC, None) => HashAlgorithm::Sha512,
+        (_, None) => HashAlgorithm::Sha384,
     };
 
-    let req = mk_request(config, &private_key)?;
+    hash_algorithm.message_digest()
+}
+
+/// Whether the PEM or DER encoded private key at the given path is encrypted, and so can only be
+/// loaded with a passphrase.
+pub fn private_key_is_encrypted(private_key_path: &Path) -> Result<bool, Error> {
+    let private_key_bytes: Vec<u8> = fs::read(private_key_path)?;
+
+    if private_key_bytes.starts_with(b"-----BEGIN") {
+        return Ok(String::from_utf8_lossy(&private_key_bytes).contains("ENCRYPTED"));
+    }
+
+    // Encrypted DER keys are always PKCS#8, anything else that does not parse is not a key.
+    match PKey::private_key_from_der(&private_key_bytes) {
+        Ok(_) => Ok(false),
+        Err(_) if ffi::is_encrypted_private_key_info(&private_key_bytes) => Ok(true),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Load an existing PEM or DER encoded private key, decrypting it with the passphrase if one is
+/// given.
+fn load_private_key(
+    private_key_path: &Path,
+    passphrase: Option<&str>,
+) -> Result<PKey<Private>, Error> {
+    let private_key_bytes: Vec<u8> = fs::read(private_key_path)?;
+    let is_pem = private_key_bytes.starts_with(b"-----BEGIN");
+
+    match (is_pem, passphrase) {
+        (true, None) => Ok(PKey::private_key_from_pem(&private_key_bytes)?),
+        (false, None) => Ok(PKey::private_key_from_der(&private_key_bytes)?),
+        (true, Some(passphrase)) => {
+            PKey::private_key_from_pem_passphrase(&private_key_bytes, passphrase.as_bytes())
+                .map_err(|_| Error::PrivateKeyDecryptionFailed(private_key_path.to_path_buf()))
+        }
+        (false, Some(passphrase)) => {
+            PKey::private_key_from_pkcs8_passphrase(&private_key_bytes, passphrase.as_bytes())
+                .map_err(|_| Error::PrivateKeyDecryptionFailed(private_key_path.to_path_buf()))
+        }
+    }
+}
+
+fn load_ca(config: &Config) -> Result<(X509, PKey<Private>), Error> {
+    let pkey = load_private_key(
+        config.signer_private_key_path.as_ref().unwrap(),
+        config.signer_private_key_passphrase.as_deref(),
+    )?;
+
+    let cert = load_certificate(config.signer_certificate_path.as_ref().unwrap())?;
+
+    check_signer_certificate(&cert)?;
+    check_signer_key(&cert, &pkey)?;
+
+    Ok((cert, pkey))
+}
+
+/// Make sure the signer private key belongs to the signer certificate, as certificates signed with
+/// another key would not verify against it.
+fn check_signer_key(
+    signer_certificate: &X509,
+    signer_private_key: &PKeyRef<Private>,
+) -> Result<(), Error> {
+    match signer_certificate
+        .public_key()?
+        .public_eq(signer_private_key)
+    {
+        true => Ok(()),
+        false => Err(Error::SignerKeyMismatch),
+    }
+}
+
+/// Make sure the signer certificate can sign certificates: it must be a CA certificate whose key
+/// usage includes keyCertSign and which is currently valid.
+pub fn check_signer_certificate(signer_certificate: &X509) -> Result<(), Error> {
+    if !ffi::is_ca(signer_certificate) {
+        return Err(Error::SignerNotCa);
+    }
+
+    if !ffi::allows_key_cert_sign(signer_certificate) {
+        return Err(Error::SignerCannotSignCertificates);
+    }
+
+    let now = Utc::now();
+    let not_before = to_timestamp(signer_certificate.not_before())?;
+    let not_after = to_timestamp(signer_certificate.not_after())?;
+
+    if now < not_before {
+        return Err(Error::SignerNotYetValid(not_before));
+    }
+
+    if now > not_after {
+        return Err(Error::SignerExpired(not_after));
+    }
+
+    Ok(())
+}
+
+/// Load an existing PEM or DER encoded SubjectPublicKeyInfo, e.g. of a key pair generated by
+/// hardware that cannot produce a certificate signing request.
+pub fn load_public_key(public_key_path: &Path) -> Result<PKey<Public>, Error> {
+    let public_key_bytes: Vec<u8> = fs::read(public_key_path)?;
+
+    match public_key_bytes.starts_with(b"-----BEGIN") {
+        true => Ok(PKey::public_key_from_pem(&public_key_bytes)?),
+        false => Ok(PKey::public_key_from_der(&public_key_bytes)?),
+    }
+}
+
+/// Whether the certificate is a CA certificate.
+pub fn is_ca(certificate: &X509) -> bool {
+    ffi::is_ca(certificate)
+}
+
+/// The pathLenConstraint of the CA certificate, if it has one.
+pub fn path_length(certificate: &X509) -> Option<u32> {
+    ffi::path_length(certificate)
+}
+
+/// Load an existing PEM or DER encoded certificate.
+pub fn load_certificate(certificate_path: &Path) -> Result<X509, Error> {
+    let cert_bytes: Vec<u8> = fs::read(certificate_path)?;
+
+    match cert_bytes.starts_with(b"-----BEGIN") {
+        true => Ok(X509::from_pem(&cert_bytes)?),
+        false => Ok(X509::from_der(&cert_bytes)?),
+    }
+}
+
+/// Convert a certificate time such as notAfter to a timestamp.
+pub fn to_timestamp(time: &Asn1TimeRef) -> Result<DateTime<Utc>, ErrorStack> {
+    let since_epoch = Asn1Time::from_unix(0)?.diff(time)?;
+    let seconds = i64::from(since_epoch.days) * 24 * 60 * 60 + i64::from(since_epoch.secs);
+
+    Ok(Utc.timestamp(seconds, 0))
+}
+
+// /// Make a certificate and private key signed by the given CA cert and private key
+pub fn create_certificate(config: &Config) -> Result<Credentials, Error> {
+    let (private_key, public_key) = load_subject_key(
+        &config.subject_key,
+        config.existing_private_key_passphrase.as_deref(),
+    )?;
+
+    let x509_name = config
+        .subject
+        .as_ref()
+        .expect("created certificates are only made for a given subject")
+        .to_x509_name()?;
     let mut cert_builder = X509::builder()?;
     cert_builder.set_version(2)?;
-    let serial_number = {
-        let mut serial = BigNum::new()?;
-        serial.rand(159, MsbOption::MAYBE_ZERO, false)?;
-        serial.to_asn1_integer()?
-    };
+    let serial_number = random_serial_number()?;
     cert_builder.set_serial_number(&serial_number)?;
-    cert_builder.set_subject_name(req.subject_name())?;
+    cert_builder.set_subject_name(&x509_name)?;
 
-    cert_builder.set_pubkey(&private_key)?;
-    let not_before = Asn1Time::days_from_now(0)?;
-    cert_builder.set_not_before(&not_before)?;
-    let not_after = Asn1Time::days_from_now(config.days_valid)?;
-    cert_builder.set_not_after(&not_after)?;
+    cert_builder.set_pubkey(&public_key)?;
+    config.validity.set(&mut cert_builder)?;
 
     cert_builder.append_extension(BasicConstraints::new().build()?)?;
 
-    cert_builder.append_extension(
-        KeyUsage::new()
-            .critical()
-            .non_repudiation()
-            .digital_signature()
-            .key_encipherment()
-            .build()?,
-    )?;
+    for extension in config.profile.extensions(&public_key)? {
+        cert_builder.append_extension(extension)?;
+    }
+
+    if !config.subject_alt_names.is_empty() {
+        let subject_alt_name = san::to_extension(
+            &config.subject_alt_names,
+            &cert_builder.x509v3_context(None, None),
+        )?;
+        cert_builder.append_extension(subject_alt_name)?;
+    }
+
+    for extension in config
+        .issuer_urls
+        .to_extensions(&cert_builder.x509v3_context(None, None))?
+    {
+        cert_builder.append_extension(extension)?;
+    }
+
+    append_policies_and_custom_extensions(&mut cert_builder, config)?;
 
-    if config.self_signed {
-        let issuer_subject_name = req.subject_name();
+    let certificate = if config.self_signed {
         let subject_key_identifier =
             SubjectKeyIdentifier::new().build(&cert_builder.x509v3_context(None, None))?;
-        cert_builder.set_issuer_name(issuer_subject_name)?;
+        cert_builder.set_issuer_name(&x509_name)?;
         cert_builder.append_extension(subject_key_identifier)?;
         let auth_key_identifier = AuthorityKeyIdentifier::new()
             .keyid(false)
             .issuer(false)
             .build(&cert_builder.x509v3_context(None, None))?;
         cert_builder.append_extension(auth_key_identifier)?;
-        cert_builder.sign(&private_key, MessageDigest::sha384())?;
+        sign(cert_builder, self_signing_key(&private_key), config)?
     } else {
-        let (signer_certificate, signer_private_key) = load_ca(config).unwrap();
+        let (signer_certificate, signer_private_key) = load_ca(config)?;
+        name_constraints::check(&signer_certificate, &x509_name, &config.subject_alt_names)?;
         let issuer_subject_name = signer_certificate.subject_name();
         let subject_key_identifier = SubjectKeyIdentifier::new()
             .build(&cert_builder.x509v3_context(Some(&signer_certificate), None))?;
@@ -232,38 +914,396 @@ pub fn create_certificate(config: &Config) -> Result<(Vec<u8>, Vec<u8>), ErrorSt
             .build(&cert_builder.x509v3_context(Some(&signer_certificate), None))?;
         cert_builder.append_extension(subject_key_identifier)?;
         cert_builder.append_extension(auth_key_identifier)?;
-        cert_builder.sign(&signer_private_key, MessageDigest::sha384())?;
-    }
+        sign(cert_builder, &signer_private_key, config)?
+    };
 
-    let certificate = cert_builder.build();
+    encode_output(&certificate, private_key.as_ref(), &public_key, config)
+}
 
-    let certificate_bytes: Vec<u8>;
-    let private_key_bytes: Vec<u8>;
-    match config.output_format {
-        FileFormat::Pem => {
-            certificate_bytes = certificate.to_pem().unwrap();
-            private_key_bytes = private_key.private_key_to_pem_pkcs8().unwrap();
-        }
-        FileFormat::Der => {
-            certificate_bytes = certificate.to_der().unwrap();
-            private_key_bytes = private_key.private_key_to_der().unwrap();
+/// Make a certificate with the subject of an existing certificate and the extensions copied from
+/// it, but with a new serial number and validity period, for the same or a new key. The key
+/// identifiers and issuer URLs are added for the new signer and key, and a copied KeyUsage is
+/// adjusted to a new key of another type.
+pub fn renew_certificate(config: &Config, certificate: &X509) -> Result<Credentials, Error> {
+    let (private_key, public_key) = load_subject_key(
+        &config.subject_key,
+        config.existing_private_key_passphrase.as_deref(),
+    )?;
+    let key_type_changed = certificate.public_key()?.id() != public_key.id();
+
+    let mut cert_builder = X509::builder()?;
+    cert_builder.set_version(2)?;
+    let serial_number = random_serial_number()?;
+    cert_builder.set_serial_number(&serial_number)?;
+    // The subject is copied exactly, so that it still matches names built from the original.
+    cert_builder.set_subject_name(certificate.subject_name())?;
+    cert_builder.set_pubkey(&public_key)?;
+    config.validity.set(&mut cert_builder)?;
+
+    for extension in &config.custom_extensions {
+        match extension.oid.as_str() {
+            KEY_USAGE_OID if key_type_changed => {
+                let value =
+                    profile::rekeyed_key_usage(&extension.value, &public_key).ok_or_else(|| {
+                        Error::InvalidExtension(
+                            "the key usage of the renewed certificate cannot be read".to_string(),
+                        )
+                    })?;
+                let key_usage = extensions::CustomExtension {
+                    value,
+                    ..extension.clone()
+                };
+                cert_builder.append_extension(key_usage.to_extension()?)?;
+            }
+            _ => cert_builder.append_extension(extension.to_extension()?)?,
         }
     }
 
-    Ok((certificate_bytes, private_key_bytes))
+    if !config
+        .custom_extensions
+        .iter()
+        .any(|extension| extension.oid == SUBJECT_KEY_IDENTIFIER_OID)
+    {
+        let subject_key_identifier =
+            SubjectKeyIdentifier::new().build(&cert_builder.x509v3_context(None, None))?;
+        cert_builder.append_extension(subject_key_identifier)?;
+    }
+
+    for extension in config
+        .issuer_urls
+        .to_extensions(&cert_builder.x509v3_context(None, None))?
+    {
+        cert_builder.append_extension(extension)?;
+    }
+
+    let (signer_certificate, signer_private_key) = load_ca(config)?;
+
+    if ffi::is_ca(certificate) {
+        check_path_length(&signer_certificate, ffi::path_length(certificate))?;
+    }
+
+    name_constraints::check(
+        &signer_certificate,
+        certificate.subject_name(),
+        &config.subject_alt_names,
+    )?;
+    cert_builder.set_issuer_name(signer_certificate.subject_name())?;
+
+    let auth_key_identifier = AuthorityKeyIdentifier::new()
+        .keyid(false)
+        .issuer(false)
+        .build(&cert_builder.x509v3_context(Some(&signer_certificate), None))?;
+    cert_builder.append_extension(auth_key_identifier)?;
+
+    let renewed_certificate = sign(cert_builder, &signer_private_key, config)?;
+
+    encode_output(
+        &renewed_certificate,
+        private_key.as_ref(),
+        &public_key,
+        config,
+    )
+}
+
+/// A random positive serial number of up to 159 bits, within the 20 octets RFC 5280 allows.
+fn random_serial_number() -> Result<Asn1Integer, ErrorStack> {
+    let mut serial = BigNum::new()?;
+    serial.rand(159, MsbOption::MAYBE_ZERO, false)?;
+    serial.to_asn1_integer()
 }
 
 /// Make a X509 request with the given private key
-fn mk_request(config: &Config, private_key: &PKey<Private>) -> Result<X509Req, ErrorStack> {
+fn mk_request(
+    subject: &name::DistinguishedName,
+    extensions: Vec<X509Extension>,
+    private_key: &PKey<Private>,
+    signature_hash: Option<HashAlgorithm>,
+) -> Result<X509Req, ErrorStack> {
     let mut req_builder = X509ReqBuilder::new()?;
     req_builder.set_pubkey(private_key)?;
 
-    let mut x509_name = X509NameBuilder::new()?;
-    x509_name.append_entry_by_text("CN", &config.common_name)?;
-    let x509_name = x509_name.build();
+    let x509_name = subject.to_x509_name()?;
     req_builder.set_subject_name(&x509_name)?;
 
-    req_builder.sign(private_key, MessageDigest::sha256())?;
+    if !extensions.is_empty() {
+        let mut extension_stack = Stack::new()?;
+
+        for extension in extensions {
+            extension_stack.push(extension)?;
+        }
+
+        req_builder.add_extensions(&extension_stack)?;
+    }
+
+    req_builder.sign(private_key, signature_digest(private_key, signature_hash))?;
     let req = req_builder.build();
     Ok(req)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn private_key() -> PKey<Private> {
+        generate_private_key(&KeyType::Ec(EcCurve::P256)).unwrap()
+    }
+
+    /// A certificate for the key that is valid from yesterday until tomorrow.
+    fn certificate(private_key: &PKey<Private>, extensions: Vec<X509Extension>) -> X509 {
+        let mut certificate = X509::builder().unwrap();
+        certificate.set_pubkey(private_key).unwrap();
+
+        Validity {
+            not_before: Utc::now() - chrono::Duration::days(1),
+            not_after: Some(Utc::now() + chrono::Duration::days(1)),
+        }
+        .set(&mut certificate)
+        .unwrap();
+
+        for extension in extensions {
+            certificate.append_extension(extension).unwrap();
+        }
+
+        certificate
+            .sign(private_key, MessageDigest::sha256())
+            .unwrap();
+        certificate.build()
+    }
+
+    fn ca_certificate(path_length: Option<u32>) -> X509 {
+        let mut basic_constraints = BasicConstraints::new();
+        basic_constraints.critical().ca();
+
+        if let Some(path_length) = path_length {
+            basic_constraints.pathlen(path_length);
+        }
+
+        certificate(&private_key(), vec![basic_constraints.build().unwrap()])
+    }
+
+    #[test]
+    fn create_certificate_request_signs_the_requested_extensions() {
+        let config = RequestConfig {
+            custom_extensions: vec![extensions::CustomExtension::parse("1.2.3.4:0500").unwrap()],
+            existing_private_key_passphrase: None,
+            export_public_key: false,
+            key_encoding: KeyEncoding::Pkcs8,
+            key_type: KeyType::Ec(EcCurve::P256),
+            output_format: FileFormat::Pem,
+            private_key_passphrase: None,
+            private_key_path: None,
+            profile: Some(profile::Profile::Client),
+            signature_hash: None,
+            subject: name::DistinguishedName::parse(
+                "CN=device.example.com",
+                name::StringEncoding::Utf8,
+            )
+            .unwrap(),
+            subject_alt_names: vec![
+                san::SubjectAltName::dns("device.example.com").unwrap(),
+                san::SubjectAltName::ip("10.0.0.1").unwrap(),
+            ],
+        };
+
+        let credentials = create_certificate_request(&config).unwrap();
+        let request = X509Req::from_pem(&credentials.request).unwrap();
+
+        assert!(request.verify(&request.public_key().unwrap()).unwrap());
+
+        let requested_extensions: Vec<extensions::CustomExtension> = request
+            .extensions()
+            .unwrap()
+            .iter()
+            .map(extensions::CustomExtension::from_extension)
+            .collect();
+        let requested_oids: Vec<&str> = requested_extensions
+            .iter()
+            .map(|extension| extension.oid.as_str())
+            .collect();
+
+        assert_eq!(
+            requested_oids,
+            vec![
+                KEY_USAGE_OID,
+                EXTENDED_KEY_USAGE_OID,
+                SUBJECT_ALT_NAME_OID,
+                "1.2.3.4"
+            ]
+        );
+        assert_eq!(requested_extensions[3].value, vec![0x05, 0x00]);
+
+        // The names are read back the way sign-csr reads them.
+        let request_path =
+            std::env::temp_dir().join(format!("tch-{}-csr.pem", uuid::Uuid::new_v4()));
+        fs::write(&request_path, &credentials.request).unwrap();
+        let loaded_request = request::CertificateRequest::load(&request_path);
+        fs::remove_file(&request_path).unwrap();
+
+        assert_eq!(
+            loaded_request
+                .unwrap()
+                .supported_subject_alt_names()
+                .unwrap(),
+            (config.subject_alt_names, 0)
+        );
+    }
+
+    fn private_key_file_is_encrypted(private_key_bytes: &[u8]) -> Result<bool, Error> {
+        let private_key_path =
+            std::env::temp_dir().join(format!("tch-{}-private-key", uuid::Uuid::new_v4()));
+        fs::write(&private_key_path, private_key_bytes).unwrap();
+
+        let is_encrypted = private_key_is_encrypted(&private_key_path);
+        fs::remove_file(&private_key_path).unwrap();
+
+        is_encrypted
+    }
+
+    #[test]
+    fn private_key_is_encrypted_only_for_encrypted_keys() {
+        let private_key = private_key();
+        let is_encrypted = |key_encoding, output_format, passphrase| {
+            private_key_file_is_encrypted(
+                &encode_private_key(&private_key, &key_encoding, &output_format, passphrase)
+                    .unwrap(),
+            )
+            .unwrap()
+        };
+
+        assert!(is_encrypted(
+            KeyEncoding::Pkcs8,
+            FileFormat::Der,
+            Some("passphrase")
+        ));
+        assert!(is_encrypted(
+            KeyEncoding::Pkcs8,
+            FileFormat::Pem,
+            Some("passphrase")
+        ));
+        assert!(!is_encrypted(KeyEncoding::Pkcs8, FileFormat::Der, None));
+        assert!(!is_encrypted(KeyEncoding::Pkcs8, FileFormat::Pem, None));
+        assert!(!is_encrypted(
+            KeyEncoding::Traditional,
+            FileFormat::Der,
+            None
+        ));
+        assert!(!is_encrypted(
+            KeyEncoding::Traditional,
+            FileFormat::Pem,
+            None
+        ));
+    }
+
+    #[test]
+    fn private_key_is_encrypted_refuses_malformed_der() {
+        let certificate_der = ca_certificate(None).to_der().unwrap();
+
+        assert!(private_key_file_is_encrypted(b"\x30\x03\x02\x01").is_err());
+        assert!(private_key_file_is_encrypted(&certificate_der).is_err());
+    }
+
+    #[test]
+    fn check_signer_certificate_accepts_a_ca_certificate_that_may_sign_certificates() {
+        let key_usage = KeyUsage::new()
+            .critical()
+            .key_cert_sign()
+            .crl_sign()
+            .build()
+            .unwrap();
+        let signer_certificate = certificate(
+            &private_key(),
+            vec![BasicConstraints::new().ca().build().unwrap(), key_usage],
+        );
+
+        assert!(check_signer_certificate(&signer_certificate).is_ok());
+    }
+
+    #[test]
+    fn check_signer_certificate_refuses_end_entity_certificates() {
+        let private_key = private_key();
+
+        for extensions in [
+            vec![],
+            vec![BasicConstraints::new().critical().build().unwrap()],
+        ] {
+            assert!(matches!(
+                check_signer_certificate(&certificate(&private_key, extensions)),
+                Err(Error::SignerNotCa)
+            ));
+        }
+    }
+
+    #[test]
+    fn check_signer_certificate_refuses_ca_certificates_without_key_cert_sign() {
+        let key_usage = KeyUsage::new()
+            .critical()
+            .digital_signature()
+            .crl_sign()
+            .build()
+            .unwrap();
+        let signer_certificate = certificate(
+            &private_key(),
+            vec![BasicConstraints::new().ca().build().unwrap(), key_usage],
+        );
+
+        assert!(matches!(
+            check_signer_certificate(&signer_certificate),
+            Err(Error::SignerCannotSignCertificates)
+        ));
+    }
+
+    #[test]
+    fn check_signer_key_refuses_a_key_of_another_certificate() {
+        let signer_private_key = private_key();
+        let signer_certificate = certificate(
+            &signer_private_key,
+            vec![BasicConstraints::new().ca().build().unwrap()],
+        );
+
+        assert!(check_signer_key(&signer_certificate, &signer_private_key).is_ok());
+        assert!(matches!(
+            check_signer_key(&signer_certificate, &private_key()),
+            Err(Error::SignerKeyMismatch)
+        ));
+    }
+
+    #[test]
+    fn check_path_length_refuses_ca_certificates_below_a_signer_with_path_length_0() {
+        let signer_certificate = ca_certificate(Some(0));
+
+        assert!(matches!(
+            check_path_length(&signer_certificate, Some(0)),
+            Err(Error::SignerPathLengthExhausted)
+        ));
+        assert!(matches!(
+            check_path_length(&signer_certificate, None),
+            Err(Error::SignerPathLengthExhausted)
+        ));
+    }
+
+    #[test]
+    fn check_path_length_refuses_path_lengths_not_below_the_signers() {
+        let signer_certificate = ca_certificate(Some(2));
+
+        assert!(check_path_length(&signer_certificate, Some(1)).is_ok());
+        assert!(check_path_length(&signer_certificate, Some(0)).is_ok());
+
+        for path_length in &[Some(2), Some(3), None] {
+            assert!(matches!(
+                check_path_length(&signer_certificate, *path_length),
+                Err(Error::PathLengthExceeded {
+                    signer_path_length: 2,
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn check_path_length_accepts_any_path_length_below_an_unconstrained_signer() {
+        let signer_certificate = ca_certificate(None);
+
+        assert!(check_path_length(&signer_certificate, Some(5)).is_ok());
+        assert!(check_path_length(&signer_certificate, None).is_ok());
+    }
+}
@@ -0,0 +1,421 @@
+/*
+Certificate policies and custom extensions given by OID. Both are encoded here as DER, since
+OpenSSL's extension configuration can only express policy qualifiers through configuration file
+sections.
+*/
+use super::ffi;
+use super::san::SubjectAltName;
+use super::{
+    AUTHORITY_INFO_ACCESS_OID, AUTHORITY_KEY_IDENTIFIER_OID, BASIC_CONSTRAINTS_OID,
+    CERTIFICATE_POLICIES_OID, CRL_DISTRIBUTION_POINTS_OID, EXTENDED_KEY_USAGE_OID, KEY_USAGE_OID,
+    NAME_CONSTRAINTS_OID, SUBJECT_ALT_NAME_OID, SUBJECT_KEY_IDENTIFIER_OID,
+};
+use crate::Error;
+use openssl::error::ErrorStack;
+use openssl::x509::{X509Extension, X509ExtensionRef, X509Ref};
+use std::fmt;
+
+/// The extensions tch sets itself, which must not be added a second time as custom extensions.
+const MANAGED_EXTENSION_OIDS: [&str; 10] = [
+    SUBJECT_KEY_IDENTIFIER_OID,
+    KEY_USAGE_OID,
+    SUBJECT_ALT_NAME_OID,
+    BASIC_CONSTRAINTS_OID,
+    NAME_CONSTRAINTS_OID,
+    CRL_DISTRIBUTION_POINTS_OID,
+    CERTIFICATE_POLICIES_OID,
+    AUTHORITY_KEY_IDENTIFIER_OID,
+    EXTENDED_KEY_USAGE_OID,
+    AUTHORITY_INFO_ACCESS_OID,
+];
+
+const CPS_QUALIFIER_OID: &str = "1.3.6.1.5.5.7.2.1";
+
+const TAG_IA5_STRING: u8 = 0x16;
+const TAG_OID: u8 = 0x06;
+const TAG_SEQUENCE: u8 = 0x30;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CertificatePolicy {
+    pub oid: String,
+    /// URIs of the certification practice statements, added as CPS qualifiers.
+    pub cps_uris: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CustomExtension {
+    pub oid: String,
+    pub critical: bool,
+    /// The DER encoded extension value, i.e. the contents of the extnValue OCTET STRING.
+    pub value: Vec<u8>,
+}
+
+impl CertificatePolicy {
+    /// Parse a policy given as `OID` or `OID:CPS-URI`.
+    pub fn parse(raw_policy: &str) -> Result<CertificatePolicy, Error> {
+        let (oid, cps_uri) = match raw_policy.find(':') {
+            Some(index) => (&raw_policy[..index], Some(&raw_policy[index + 1..])),
+            None => (raw_policy, None),
+        };
+
+        if encode_oid(oid).is_none() {
+            return Err(Error::InvalidPolicy(format!(
+                "\"{}\" is not a dotted decimal OID",
+                oid
+            )));
+        }
+
+        let cps_uris = match cps_uri {
+            Some(cps_uri) => match SubjectAltName::uri(cps_uri) {
+                Ok(_) => vec![cps_uri.to_string()],
+                Err(_) => {
+                    return Err(Error::InvalidPolicy(format!(
+                        "CPS \"{}\" is not an absolute ASCII URI",
+                        cps_uri
+                    )))
+                }
+            },
+            None => Vec::new(),
+        };
+
+        Ok(CertificatePolicy {
+            oid: oid.to_string(),
+            cps_uris,
+        })
+    }
+
+    fn to_der(&self) -> Vec<u8> {
+        let mut policy_information = encode_tlv(TAG_OID, &encode_oid(&self.oid).unwrap());
+
+        if !self.cps_uris.is_empty() {
+            let qualifiers: Vec<u8> = self
+                .cps_uris
+                .iter()
+                .flat_map(|cps_uri| {
+                    let mut qualifier =
+                        encode_tlv(TAG_OID, &encode_oid(CPS_QUALIFIER_OID).unwrap());
+                    qualifier.extend(encode_tlv(TAG_IA5_STRING, cps_uri.as_bytes()));
+                    encode_tlv(TAG_SEQUENCE, &qualifier)
+                })
+                .collect();
+
+            policy_information.extend(encode_tlv(TAG_SEQUENCE, &qualifiers));
+        }
+
+        encode_tlv(TAG_SEQUENCE, &policy_information)
+    }
+}
+
+impl fmt::Display for CertificatePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.cps_uris.is_empty() {
+            true => write!(f, "{}", self.oid),
+            false => write!(f, "{} (cps {})", self.oid, self.cps_uris.join(", ")),
+        }
+    }
+}
+
+impl CustomExtension {
+    /// Parse an extension given as `OID:critical:DER-hex`, `OID:non-critical:DER-hex` or, for a
+    /// non-critical extension, `OID:DER-hex`.
+    pub fn parse(raw_extension: &str) -> Result<CustomExtension, Error> {
+        let invalid = |reason: &str| {
+            Err(Error::InvalidExtension(format!(
+                "\"{}\" {}",
+                raw_extension, reason
+            )))
+        };
+
+        let parts: Vec<&str> = raw_extension.split(':').collect();
+
+        let (oid, critical, raw_value) = match parts.as_slice() {
+            [oid, "critical", raw_value] => (*oid, true, *raw_value),
+            [oid, "non-critical", raw_value] => (*oid, false, *raw_value),
+            [oid, raw_value] => (*oid, false, *raw_value),
+            _ => return invalid("is not of the form OID:critical:DER-hex"),
+        };
+
+        if encode_oid(oid).is_none() {
+            return invalid("does not start with a dotted decimal OID");
+        }
+
+        if MANAGED_EXTENSION_OIDS.contains(&oid) {
+            return invalid("is an extension tch sets itself, use the dedicated options instead");
+        }
+
+        let value = match decode_hex(raw_value) {
+            Some(value) => value,
+            None => return invalid("does not end with an even number of hex digits"),
+        };
+
+        if !is_single_der_value(&value) {
+            return invalid("does not contain exactly one DER encoded value");
+        }
+
+        Ok(CustomExtension {
+            oid: oid.to_string(),
+            critical,
+            value,
+        })
+    }
+
+    /// Take over an existing extension as it is, e.g. one of a certificate signing request.
+    pub(super) fn from_extension(extension: &X509ExtensionRef) -> CustomExtension {
+        let (oid, critical, value) = ffi::extension_parts(extension);
+
+        CustomExtension {
+            oid,
+            critical,
+            value,
+        }
+    }
+
+    pub fn to_extension(&self) -> Result<X509Extension, ErrorStack> {
+        der_extension(&self.oid, self.critical, &self.value)
+    }
+
+    /// Whether tch sets the extension itself, in which case it must not be copied from elsewhere.
+    pub fn is_managed(&self) -> bool {
+        MANAGED_EXTENSION_OIDS.contains(&self.oid.as_str())
+    }
+}
+
+impl fmt::Display for CustomExtension {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}, {} bytes)",
+            self.oid,
+            match self.critical {
+                true => "critical",
+                false => "non-critical",
+            },
+            self.value.len()
+        )
+    }
+}
+
+/// All extensions of the certificate, including the ones tch sets itself.
+pub fn certificate_extensions(certificate: &X509Ref) -> Vec<CustomExtension> {
+    ffi::extensions(certificate)
+        .into_iter()
+        .map(CustomExtension::from_extension)
+        .collect()
+}
+
+/// Build the non-critical CertificatePolicies extension holding the given policies.
+pub fn certificate_policies_extension(
+    policies: &[CertificatePolicy],
+) -> Result<X509Extension, ErrorStack> {
+    let policies: Vec<u8> = policies.iter().flat_map(|policy| policy.to_der()).collect();
+    der_extension(
+        CERTIFICATE_POLICIES_OID,
+        false,
+        &encode_tlv(TAG_SEQUENCE, &policies),
+    )
+}
+
+fn der_extension(oid: &str, critical: bool, value: &[u8]) -> Result<X509Extension, ErrorStack> {
+    let hex: Vec<String> = value.iter().map(|byte| format!("{:02X}", byte)).collect();
+    let conf = match critical {
+        true => format!("critical,DER:{}", hex.join(":")),
+        false => format!("DER:{}", hex.join(":")),
+    };
+
+    X509Extension::new(None, None, oid, &conf)
+}
+
+fn encode_tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut tlv = vec![tag];
+
+    match content.len() {
+        length if length < 0x80 => tlv.push(length as u8),
+        length => {
+            let length_bytes: Vec<u8> = length
+                .to_be_bytes()
+                .iter()
+                .copied()
+                .skip_while(|byte| *byte == 0)
+                .collect();
+            tlv.push(0x80 | length_bytes.len() as u8);
+            tlv.extend(length_bytes);
+        }
+    }
+
+    tlv.extend_from_slice(content);
+    tlv
+}
+
+/// Encode the contents of an OBJECT IDENTIFIER, or None if the string is not a valid dotted
+/// decimal OID.
+fn encode_oid(oid: &str) -> Option<Vec<u8>> {
+    let arcs: Vec<u64> = oid
+        .split('.')
+        .map(|arc| match arc.chars().all(|c| c.is_ascii_digit()) {
+            true => arc.parse().ok(),
+            false => None,
+        })
+        .collect::<Option<Vec<u64>>>()?;
+
+    match arcs.as_slice() {
+        [first, second, ..] if *first < 2 && *second < 40 => (),
+        [2, second, ..] if *second <= u64::MAX - 80 => (),
+        _ => return None,
+    }
+
+    let mut content = Vec::new();
+
+    for arc in std::iter::once(arcs[0] * 40 + arcs[1]).chain(arcs[2..].iter().copied()) {
+        let mut base128 = vec![(arc & 0x7f) as u8];
+        let mut rest = arc >> 7;
+
+        while rest > 0 {
+            base128.push((rest & 0x7f) as u8 | 0x80);
+            rest >>= 7;
+        }
+
+        content.extend(base128.iter().rev());
+    }
+
+    Some(content)
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.is_empty() || hex.len() & 1 == 1 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&hex[index..index + 2], 16).ok())
+        .collect()
+}
+
+/// Whether the bytes are exactly one DER tag-length-value with a single byte tag.
+fn is_single_der_value(der: &[u8]) -> bool {
+    let (length, header_length) = match der.get(1) {
+        Some(length) if *length < 0x80 => (usize::from(*length), 2),
+        Some(length) => {
+            let length_bytes = usize::from(*length & 0x7f);
+
+            if length_bytes == 0 || length_bytes > 4 || der.len() < 2 + length_bytes {
+                return false;
+            }
+
+            let length = der[2..2 + length_bytes]
+                .iter()
+                .fold(0, |length, byte| (length << 8) | usize::from(*byte));
+
+            // DER lengths use the long form only when they must, and without leading zeros.
+            if der[2] == 0 || length < 0x80 {
+                return false;
+            }
+            (length, 2 + length_bytes)
+        }
+        None => return false,
+    };
+
+    der[0] & 0x1f != 0x1f && der.len() == header_length + length
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_oid_encodes_first_two_arcs_together() {
+        assert_eq!(
+            encode_oid("1.2.840.113549"),
+            Some(vec![0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d])
+        );
+        assert_eq!(encode_oid("0.0"), Some(vec![0x00]));
+        assert_eq!(encode_oid("2.5.29.17"), Some(vec![0x55, 0x1d, 0x11]));
+    }
+
+    #[test]
+    fn encode_oid_encodes_large_second_arc_under_joint_iso_itu_t() {
+        assert_eq!(encode_oid("2.999"), Some(vec![0x88, 0x37]));
+        assert_eq!(encode_oid("2.999.3"), Some(vec![0x88, 0x37, 0x03]));
+    }
+
+    #[test]
+    fn encode_oid_rejects_invalid_oids() {
+        for oid in &[
+            "", "1", "3.1", "1.40", "0.39.", "1..2", "1.2.a", "+1.2", "1.2.-3",
+        ] {
+            assert_eq!(encode_oid(oid), None, "{}", oid);
+        }
+        assert_eq!(encode_oid("1.39"), Some(vec![0x4f]));
+    }
+
+    #[test]
+    fn encode_tlv_uses_short_form_below_128_bytes() {
+        assert_eq!(encode_tlv(TAG_SEQUENCE, &[]), vec![0x30, 0x00]);
+        assert_eq!(encode_tlv(TAG_SEQUENCE, &[0; 127])[..2], [0x30, 0x7f]);
+    }
+
+    #[test]
+    fn encode_tlv_uses_minimal_long_form_lengths() {
+        assert_eq!(encode_tlv(TAG_SEQUENCE, &[0; 128])[..3], [0x30, 0x81, 0x80]);
+        assert_eq!(encode_tlv(TAG_SEQUENCE, &[0; 255])[..3], [0x30, 0x81, 0xff]);
+        assert_eq!(
+            encode_tlv(TAG_SEQUENCE, &[0; 256])[..4],
+            [0x30, 0x82, 0x01, 0x00]
+        );
+        assert_eq!(encode_tlv(TAG_SEQUENCE, &[0; 256]).len(), 260);
+    }
+
+    #[test]
+    fn is_single_der_value_accepts_one_value() {
+        assert!(is_single_der_value(&[0x05, 0x00]));
+        assert!(is_single_der_value(&encode_tlv(TAG_SEQUENCE, &[0; 128])));
+        assert!(is_single_der_value(&encode_tlv(TAG_SEQUENCE, &[0; 256])));
+    }
+
+    #[test]
+    fn is_single_der_value_rejects_malformed_values() {
+        // Empty, truncated, and trailing data.
+        assert!(!is_single_der_value(&[]));
+        assert!(!is_single_der_value(&[0x05]));
+        assert!(!is_single_der_value(&[0x04, 0x02, 0x00]));
+        assert!(!is_single_der_value(&[0x05, 0x00, 0x05, 0x00]));
+        // Indefinite, non-minimal and oversized long form lengths.
+        assert!(!is_single_der_value(&[0x30, 0x80, 0x00, 0x00]));
+        assert!(!is_single_der_value(&[0x04, 0x81, 0x01, 0x00]));
+        assert!(!is_single_der_value(&[0x04, 0x82, 0x00, 0x01, 0x00]));
+        assert!(!is_single_der_value(&[0x04, 0x85, 0, 0, 0, 0, 1, 0]));
+        // Multi-byte tags.
+        assert!(!is_single_der_value(&[0x1f, 0x01, 0x00]));
+    }
+
+    #[test]
+    fn parse_accepts_all_criticality_forms() {
+        let critical = CustomExtension::parse("1.2.3.4:critical:0500").unwrap();
+        let non_critical = CustomExtension::parse("1.2.3.4:non-critical:0500").unwrap();
+        let default = CustomExtension::parse("1.2.3.4:0500").unwrap();
+
+        assert!(critical.critical);
+        assert!(!non_critical.critical);
+        assert!(!default.critical);
+        assert_eq!(default.value, vec![0x05, 0x00]);
+    }
+
+    #[test]
+    fn parse_rejects_invalid_extensions() {
+        for raw_extension in &[
+            "1.2.3.4",
+            "1.2.3.4:maybe:0500",
+            "1.2.3.4:050",
+            "1.2.3.4:zz00",
+            "1.2.3.4:05000500",
+            "x.y:0500",
+            "2.5.29.17:0500",
+        ] {
+            assert!(
+                CustomExtension::parse(raw_extension).is_err(),
+                "{}",
+                raw_extension
+            );
+        }
+    }
+}
@@ -0,0 +1,230 @@
+/*
+Bindings for the few pieces of OpenSSL functionality that the openssl crate does not expose.
+*/
+use foreign_types::{ForeignType, ForeignTypeRef};
+use openssl::error::ErrorStack;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, PKeyRef, Private};
+use openssl::rsa::Rsa;
+use openssl::x509::{X509ExtensionRef, X509Ref, X509VerifyResult, X509};
+use std::mem;
+use std::os::raw::{c_char, c_int, c_long, c_void};
+use std::ptr;
+use std::slice;
+
+/// Makes OpenSSL use a salt as long as the digest for RSASSA-PSS signatures.
+const RSA_PSS_SALTLEN_DIGEST: c_int = -1;
+
+extern "C" {
+    fn X509_sign_ctx(x: *mut openssl_sys::X509, ctx: *mut openssl_sys::EVP_MD_CTX) -> c_int;
+    fn X509_get_pathlen(x: *mut openssl_sys::X509) -> c_long;
+    fn X509_check_purpose(x: *mut openssl_sys::X509, id: c_int, ca: c_int) -> c_int;
+    fn NAME_CONSTRAINTS_check(x: *mut openssl_sys::X509, nc: *mut c_void) -> c_int;
+    fn NAME_CONSTRAINTS_check_CN(x: *mut openssl_sys::X509, nc: *mut c_void) -> c_int;
+    fn NAME_CONSTRAINTS_free(nc: *mut c_void);
+    fn d2i_X509_SIG(a: *mut *mut c_void, pp: *mut *const u8, length: c_long) -> *mut c_void;
+    fn X509_SIG_free(a: *mut c_void);
+}
+
+fn cvt(result: c_int) -> Result<c_int, ErrorStack> {
+    if result <= 0 {
+        Err(ErrorStack::get())
+    } else {
+        Ok(result)
+    }
+}
+
+/// Convert an RSA key into an RSASSA-PSS key, whose SubjectPublicKeyInfo uses the id-RSASSA-PSS
+/// algorithm identifier and which can only ever produce PSS signatures.
+pub fn rsa_pss_key_from_rsa(rsa: Rsa<Private>) -> Result<PKey<Private>, ErrorStack> {
+    unsafe {
+        let pkey_ptr = openssl_sys::EVP_PKEY_new();
+        if pkey_ptr.is_null() {
+            return Err(ErrorStack::get());
+        }
+        let pkey = PKey::from_ptr(pkey_ptr);
+        cvt(openssl_sys::EVP_PKEY_assign(
+            pkey.as_ptr(),
+            openssl_sys::NID_rsassaPss,
+            rsa.as_ptr() as *mut c_void,
+        ))?;
+        // The EVP_PKEY now owns the RSA key.
+        mem::forget(rsa);
+        Ok(pkey)
+    }
+}
+
+/// Whether the key is an RSASSA-PSS key, which the openssl crate has no `Id` for.
+pub fn is_rsa_pss_key<T>(key: &PKeyRef<T>) -> bool {
+    key.id().as_raw() == openssl_sys::NID_rsassaPss
+}
+
+/// Sign the certificate with an RSA or RSASSA-PSS key using RSASSA-PSS padding.
+pub fn sign_with_pss_padding(
+    certificate: &mut X509,
+    signing_key: &PKeyRef<Private>,
+    digest: MessageDigest,
+) -> Result<(), ErrorStack> {
+    unsafe {
+        let md_ctx = openssl_sys::EVP_MD_CTX_new();
+        if md_ctx.is_null() {
+            return Err(ErrorStack::get());
+        }
+
+        let mut pkey_ctx = ptr::null_mut();
+        let result = cvt(openssl_sys::EVP_DigestSignInit(
+            md_ctx,
+            &mut pkey_ctx,
+            digest.as_ptr(),
+            ptr::null_mut(),
+            signing_key.as_ptr(),
+        ))
+        // The key type is left unspecified (-1) so that both RSA and RSASSA-PSS keys are accepted.
+        .and_then(|_| {
+            cvt(openssl_sys::EVP_PKEY_CTX_ctrl(
+                pkey_ctx,
+                -1,
+                -1,
+                openssl_sys::EVP_PKEY_CTRL_RSA_PADDING,
+                openssl_sys::RSA_PKCS1_PSS_PADDING,
+                ptr::null_mut(),
+            ))
+        })
+        .and_then(|_| {
+            cvt(openssl_sys::EVP_PKEY_CTX_ctrl(
+                pkey_ctx,
+                -1,
+                openssl_sys::EVP_PKEY_OP_TYPE_SIG,
+                openssl_sys::EVP_PKEY_CTRL_RSA_PSS_SALTLEN,
+                RSA_PSS_SALTLEN_DIGEST,
+                ptr::null_mut(),
+            ))
+        })
+        .and_then(|_| cvt(X509_sign_ctx(certificate.as_ptr(), md_ctx)));
+
+        openssl_sys::EVP_MD_CTX_free(md_ctx);
+        result.map(|_| ())
+    }
+}
+
+/// The pathLenConstraint of the certificate's BasicConstraints, if it has one.
+pub fn path_length(certificate: &X509Ref) -> Option<u32> {
+    let path_length = unsafe { X509_get_pathlen(certificate.as_ptr()) };
+
+    match path_length {
+        -1 => None,
+        path_length => Some(path_length as u32),
+    }
+}
+
+/// Check the names of the certificate against the NameConstraints of the signer certificate, if it
+/// has any. Like verifiers do, a common name that looks like a hostname is checked as a DNS name.
+pub fn check_name_constraints(
+    signer_certificate: &X509Ref,
+    certificate: &X509Ref,
+) -> Result<(), X509VerifyResult> {
+    unsafe {
+        let name_constraints = openssl_sys::X509_get_ext_d2i(
+            signer_certificate.as_ptr(),
+            openssl_sys::NID_name_constraints,
+            ptr::null_mut(),
+            ptr::null_mut(),
+        );
+        if name_constraints.is_null() {
+            return Ok(());
+        }
+
+        // The check reads the subject alternative names from the certificate's extension cache,
+        // which OpenSSL only fills in once the certificate's purpose is checked.
+        X509_check_purpose(certificate.as_ptr(), -1, 0);
+        let result = match NAME_CONSTRAINTS_check(certificate.as_ptr(), name_constraints) {
+            openssl_sys::X509_V_OK => {
+                NAME_CONSTRAINTS_check_CN(certificate.as_ptr(), name_constraints)
+            }
+            result => result,
+        };
+        NAME_CONSTRAINTS_free(name_constraints);
+
+        match result {
+            openssl_sys::X509_V_OK => Ok(()),
+            result => Err(X509VerifyResult::from_raw(result)),
+        }
+    }
+}
+
+/// Whether the DER encoded bytes hold a PKCS#8 EncryptedPrivateKeyInfo, which OpenSSL parses as an
+/// X509_SIG as both are an algorithm identifier followed by an octet string.
+pub fn is_encrypted_private_key_info(der: &[u8]) -> bool {
+    unsafe {
+        let mut der_ptr = der.as_ptr();
+        let encrypted_private_key_info =
+            d2i_X509_SIG(ptr::null_mut(), &mut der_ptr, der.len() as c_long);
+
+        if encrypted_private_key_info.is_null() {
+            // Drop the parse error OpenSSL queued, so that it is not reported with a later error.
+            ErrorStack::get();
+            return false;
+        }
+
+        X509_SIG_free(encrypted_private_key_info);
+        true
+    }
+}
+
+/// Whether the certificate has BasicConstraints with cA set.
+pub fn is_ca(certificate: &X509Ref) -> bool {
+    let flags = unsafe { openssl_sys::X509_get_extension_flags(certificate.as_ptr()) };
+    let ca_flags = openssl_sys::EXFLAG_BCONS | openssl_sys::EXFLAG_CA;
+
+    flags & ca_flags == ca_flags
+}
+
+/// Whether the certificate's key may sign certificates. Like OpenSSL, a certificate without a
+/// KeyUsage extension is not restricted.
+pub fn allows_key_cert_sign(certificate: &X509Ref) -> bool {
+    let key_usage = unsafe { openssl_sys::X509_get_key_usage(certificate.as_ptr()) };
+
+    key_usage & openssl_sys::X509v3_KU_KEY_CERT_SIGN != 0
+}
+
+/// The extensions of the certificate, in order.
+pub fn extensions(certificate: &X509Ref) -> Vec<&X509ExtensionRef> {
+    unsafe {
+        let count = openssl_sys::X509_get_ext_count(certificate.as_ptr());
+
+        (0..count)
+            .map(|index| {
+                X509ExtensionRef::from_ptr(openssl_sys::X509_get_ext(certificate.as_ptr(), index))
+            })
+            .collect()
+    }
+}
+
+/// The dotted decimal OID of the extension, whether it is critical, and its DER encoded value.
+pub fn extension_parts(extension: &X509ExtensionRef) -> (String, bool, Vec<u8>) {
+    unsafe {
+        let object = openssl_sys::X509_EXTENSION_get_object(extension.as_ptr());
+
+        // The first call only measures the OID, whose length OBJ_obj2txt returns.
+        let length = openssl_sys::OBJ_obj2txt(ptr::null_mut(), 0, object, 1);
+        let mut oid = vec![0u8; length.max(0) as usize + 1];
+        openssl_sys::OBJ_obj2txt(
+            oid.as_mut_ptr() as *mut c_char,
+            oid.len() as c_int,
+            object,
+            1,
+        );
+        oid.truncate(length.max(0) as usize);
+
+        let critical = openssl_sys::X509_EXTENSION_get_critical(extension.as_ptr()) == 1;
+
+        let data = openssl_sys::X509_EXTENSION_get_data(extension.as_ptr());
+        let value = slice::from_raw_parts(
+            openssl_sys::ASN1_STRING_get0_data(data as *const openssl_sys::ASN1_STRING),
+            openssl_sys::ASN1_STRING_length(data as *const openssl_sys::ASN1_STRING) as usize,
+        )
+        .to_vec();
+
+        (String::from_utf8_lossy(&oid).into_owned(), critical, value)
+    }
+}
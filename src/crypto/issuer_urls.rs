@@ -0,0 +1,257 @@
+/*
+Issuer URLs: where relying parties find revocation information and the issuer's certificate, carried
+in the CRL Distribution Points and Authority Information Access extensions. A CA's URLs are kept in a
+file next to its certificate so that every certificate it issues carries them.
+*/
+use super::san::SubjectAltName;
+use crate::Error;
+use openssl::error::ErrorStack;
+use openssl::nid::Nid;
+use openssl::x509::{X509Extension, X509v3Context};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The keys of the issuer URL file, named like the CLI options.
+const CA_ISSUERS_KEY: &str = "ca-issuers-url";
+const CRL_DISTRIBUTION_POINT_KEY: &str = "crl-distribution-point";
+const OCSP_KEY: &str = "ocsp-url";
+
+#[derive(Debug, Clone, Default)]
+pub struct IssuerUrls {
+    /// Where the signer's certificate can be fetched from, for clients that build chains on demand.
+    pub ca_issuers: Vec<String>,
+    /// Where the signer publishes its certificate revocation lists.
+    pub crl_distribution_points: Vec<String>,
+    /// The signer's OCSP responders.
+    pub ocsp: Vec<String>,
+}
+
+impl IssuerUrls {
+    /// The issuer URL file of the CA certificate at the given path, e.g.
+    /// `intermediate-ca-certificate.issuer-urls` for `intermediate-ca-certificate.pem`.
+    pub fn file_path(certificate_path: &Path) -> PathBuf {
+        certificate_path.with_extension("issuer-urls")
+    }
+
+    /// Read an issuer URL file, if there is one. Each line holds an option name and a URL, e.g.
+    /// `ocsp-url http://ocsp.example.com`. Blank lines and lines starting with # are ignored.
+    pub fn load(path: &Path) -> Result<Option<IssuerUrls>, Error> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut issuer_urls = IssuerUrls::default();
+
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = |reason: String| Error::InvalidIssuerUrlsFile {
+                path: path.to_path_buf(),
+                line: index + 1,
+                reason,
+            };
+
+            let mut parts = line.split_whitespace();
+            let (key, raw_url) = match (parts.next(), parts.next(), parts.next()) {
+                (Some(key), Some(raw_url), None) => (key, raw_url),
+                _ => return Err(invalid("expected an option name and a URL".to_string())),
+            };
+            let url = IssuerUrls::parse_url(raw_url).map_err(|err| invalid(err.to_string()))?;
+
+            match key {
+                CA_ISSUERS_KEY => issuer_urls.ca_issuers.push(url),
+                CRL_DISTRIBUTION_POINT_KEY => issuer_urls.crl_distribution_points.push(url),
+                OCSP_KEY => issuer_urls.ocsp.push(url),
+                _ => return Err(invalid(format!("unknown option name \"{}\"", key))),
+            }
+        }
+
+        Ok(Some(issuer_urls))
+    }
+
+    /// Render the URLs in the format `load` reads.
+    pub fn to_file(&self) -> String {
+        self.crl_distribution_points
+            .iter()
+            .map(|url| (CRL_DISTRIBUTION_POINT_KEY, url))
+            .chain(self.ocsp.iter().map(|url| (OCSP_KEY, url)))
+            .chain(self.ca_issuers.iter().map(|url| (CA_ISSUERS_KEY, url)))
+            .map(|(key, url)| format!("{} {}\n", key, url))
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ca_issuers.is_empty()
+            && self.crl_distribution_points.is_empty()
+            && self.ocsp.is_empty()
+    }
+
+    /// Validate a URL given for one of the extensions. Only the schemes relying parties commonly
+    /// fetch from are accepted, and commas are rejected as they separate values in the extension
+    /// configuration.
+    pub fn parse_url(raw_url: &str) -> Result<String, Error> {
+        let scheme = raw_url.split(':').next().unwrap_or("").to_ascii_lowercase();
+
+        match SubjectAltName::uri(raw_url) {
+            Ok(_)
+                if ["http", "https", "ldap"].contains(&scheme.as_str())
+                    && !raw_url.contains(',') =>
+            {
+                Ok(raw_url.to_string())
+            }
+            _ => Err(Error::InvalidUrl(raw_url.to_string())),
+        }
+    }
+
+    /// Build the CRL Distribution Points and Authority Information Access extensions for the URLs
+    /// that were given, one distribution point per CRL URL.
+    pub fn to_extensions(&self, context: &X509v3Context) -> Result<Vec<X509Extension>, ErrorStack> {
+        let mut extensions = Vec::new();
+
+        if !self.crl_distribution_points.is_empty() {
+            let conf: Vec<String> = self
+                .crl_distribution_points
+                .iter()
+                .map(|url| format!("URI:{}", url))
+                .collect();
+
+            extensions.push(X509Extension::new_nid(
+                None,
+                Some(context),
+                Nid::CRL_DISTRIBUTION_POINTS,
+                &conf.join(","),
+            )?);
+        }
+
+        if !self.ocsp.is_empty() || !self.ca_issuers.is_empty() {
+            let conf: Vec<String> = self
+                .ocsp
+                .iter()
+                .map(|url| format!("OCSP;URI:{}", url))
+                .chain(
+                    self.ca_issuers
+                        .iter()
+                        .map(|url| format!("caIssuers;URI:{}", url)),
+                )
+                .collect();
+
+            extensions.push(X509Extension::new_nid(
+                None,
+                Some(context),
+                Nid::INFO_ACCESS,
+                &conf.join(","),
+            )?);
+        }
+
+        Ok(extensions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn load(contents: &str) -> Result<Option<IssuerUrls>, Error> {
+        let path = std::env::temp_dir().join(format!("tch-{}.issuer-urls", Uuid::new_v4()));
+        fs::write(&path, contents).unwrap();
+
+        let issuer_urls = IssuerUrls::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        issuer_urls
+    }
+
+    fn invalid_line(contents: &str) -> usize {
+        match load(contents) {
+            Err(Error::InvalidIssuerUrlsFile { line, .. }) => line,
+            result => panic!("unexpected result {:?}", result.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn load_reads_a_hand_written_file() {
+        let issuer_urls = load(
+            "\
+# Root CA
+crl-distribution-point http://crl.example.com/root.crl
+
+ocsp-url   http://ocsp.example.com
+  ca-issuers-url https://example.com/root.cer
+crl-distribution-point ldap://ldap.example.com/cn=root
+",
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(
+            issuer_urls.crl_distribution_points,
+            vec![
+                "http://crl.example.com/root.crl",
+                "ldap://ldap.example.com/cn=root"
+            ]
+        );
+        assert_eq!(issuer_urls.ocsp, vec!["http://ocsp.example.com"]);
+        assert_eq!(issuer_urls.ca_issuers, vec!["https://example.com/root.cer"]);
+    }
+
+    #[test]
+    fn load_reads_back_what_to_file_writes() {
+        let issuer_urls = IssuerUrls {
+            ca_issuers: vec!["http://example.com/root.cer".to_string()],
+            crl_distribution_points: vec!["http://crl.example.com/root.crl".to_string()],
+            ocsp: vec!["http://ocsp.example.com".to_string()],
+        };
+
+        let loaded = load(&issuer_urls.to_file()).unwrap().unwrap();
+
+        assert_eq!(loaded.to_file(), issuer_urls.to_file());
+    }
+
+    #[test]
+    fn load_treats_a_missing_file_as_no_urls() {
+        let path = std::env::temp_dir().join(format!("tch-{}.issuer-urls", Uuid::new_v4()));
+
+        assert!(IssuerUrls::load(&path).unwrap().is_none());
+    }
+
+    #[test]
+    fn load_refuses_unknown_option_names() {
+        assert_eq!(
+            invalid_line("ocsp-url http://ocsp.example.com\nocsp http://ocsp.example.com\n"),
+            2
+        );
+    }
+
+    #[test]
+    fn load_refuses_malformed_lines() {
+        assert_eq!(invalid_line("ocsp-url\n"), 1);
+        assert_eq!(
+            invalid_line("\nocsp-url http://a.example.com http://b.example.com\n"),
+            2
+        );
+    }
+
+    #[test]
+    fn load_refuses_urls_that_cannot_be_fetched() {
+        assert_eq!(invalid_line("ocsp-url ftp://ocsp.example.com\n"), 1);
+        assert_eq!(invalid_line("ca-issuers-url example.com/root.cer\n"), 1);
+    }
+
+    #[test]
+    fn parse_url_accepts_http_https_and_ldap_only() {
+        assert!(IssuerUrls::parse_url("http://crl.example.com/root.crl").is_ok());
+        assert!(IssuerUrls::parse_url("HTTPS://example.com/root.cer").is_ok());
+        assert!(IssuerUrls::parse_url("ldap://ldap.example.com/cn=root").is_ok());
+        assert!(IssuerUrls::parse_url("file:///etc/root.crl").is_err());
+        assert!(IssuerUrls::parse_url("//example.com/root.crl").is_err());
+        assert!(IssuerUrls::parse_url("http://example.com/a,b").is_err());
+        assert!(IssuerUrls::parse_url("http://example.com/a b").is_err());
+    }
+}
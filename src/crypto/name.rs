@@ -0,0 +1,651 @@
+/*
+Distinguished names: parsing of RFC 4514 strings, validation of attribute values, and conversion to
+OpenSSL X509 names.
+*/
+use crate::Error;
+use openssl::asn1::Asn1Type;
+use openssl::error::ErrorStack;
+use openssl::nid::Nid;
+use openssl::x509::{X509Name, X509NameBuilder, X509NameRef};
+use std::fmt;
+use strum_macros::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Attribute {
+    CommonName,
+    Country,
+    EmailAddress,
+    Locality,
+    Organization,
+    OrganizationalUnit,
+    SerialNumber,
+    State,
+}
+
+/// The ASN.1 string type used for attributes that allow a choice of encoding.
+#[derive(Display, Debug, Clone, Copy)]
+pub enum StringEncoding {
+    #[strum(to_string = "printable")]
+    Printable,
+    #[strum(to_string = "utf8")]
+    Utf8,
+}
+
+#[derive(Debug, Clone)]
+pub struct DistinguishedName {
+    /// The attributes in encoding order, i.e. the most significant (usually the country) first.
+    pub attributes: Vec<(Attribute, String)>,
+    pub encoding: StringEncoding,
+}
+
+impl Attribute {
+    /// The attribute type as written in RFC 4514 strings.
+    fn short_name(self) -> &'static str {
+        match self {
+            Attribute::CommonName => "CN",
+            Attribute::Country => "C",
+            Attribute::EmailAddress => "emailAddress",
+            Attribute::Locality => "L",
+            Attribute::Organization => "O",
+            Attribute::OrganizationalUnit => "OU",
+            Attribute::SerialNumber => "serialNumber",
+            Attribute::State => "ST",
+        }
+    }
+
+    fn from_short_name(short_name: &str) -> Option<Attribute> {
+        match short_name.to_ascii_lowercase().as_str() {
+            "cn" => Some(Attribute::CommonName),
+            "c" => Some(Attribute::Country),
+            "emailaddress" | "email" | "e" => Some(Attribute::EmailAddress),
+            "l" => Some(Attribute::Locality),
+            "o" => Some(Attribute::Organization),
+            "ou" => Some(Attribute::OrganizationalUnit),
+            "serialnumber" => Some(Attribute::SerialNumber),
+            "st" => Some(Attribute::State),
+            _ => None,
+        }
+    }
+
+    fn from_nid(nid: Nid) -> Option<Attribute> {
+        [
+            Attribute::CommonName,
+            Attribute::Country,
+            Attribute::EmailAddress,
+            Attribute::Locality,
+            Attribute::Organization,
+            Attribute::OrganizationalUnit,
+            Attribute::SerialNumber,
+            Attribute::State,
+        ]
+        .iter()
+        .copied()
+        .find(|attribute| attribute.nid() == nid)
+    }
+
+    fn nid(self) -> Nid {
+        match self {
+            Attribute::CommonName => Nid::COMMONNAME,
+            Attribute::Country => Nid::COUNTRYNAME,
+            Attribute::EmailAddress => Nid::PKCS9_EMAILADDRESS,
+            Attribute::Locality => Nid::LOCALITYNAME,
+            Attribute::Organization => Nid::ORGANIZATIONNAME,
+            Attribute::OrganizationalUnit => Nid::ORGANIZATIONALUNITNAME,
+            Attribute::SerialNumber => Nid::SERIALNUMBER,
+            Attribute::State => Nid::STATEORPROVINCENAME,
+        }
+    }
+
+    /// The upper bound on the value length given in RFC 5280 appendix A.
+    fn max_length(self) -> usize {
+        match self {
+            Attribute::CommonName => 64,
+            Attribute::Country => 2,
+            Attribute::EmailAddress => 255,
+            Attribute::Locality => 128,
+            Attribute::Organization => 64,
+            Attribute::OrganizationalUnit => 64,
+            Attribute::SerialNumber => 64,
+            Attribute::State => 128,
+        }
+    }
+
+    /// The ASN.1 string type of the attribute value. Country names and serial numbers are always
+    /// PrintableString and email addresses always IA5String.
+    fn asn1_type(self, encoding: StringEncoding) -> Asn1Type {
+        match (self, encoding) {
+            (Attribute::Country, _) | (Attribute::SerialNumber, _) => Asn1Type::PRINTABLESTRING,
+            (Attribute::EmailAddress, _) => Asn1Type::IA5STRING,
+            (_, StringEncoding::Printable) => Asn1Type::PRINTABLESTRING,
+            (_, StringEncoding::Utf8) => Asn1Type::UTF8STRING,
+        }
+    }
+}
+
+impl DistinguishedName {
+    /// Parse an RFC 4514 string such as `CN=device,O=Very,C=US`. RFC 4514 strings list the least
+    /// significant attribute first, so the order is reversed for encoding.
+    pub fn parse(raw_name: &str, encoding: StringEncoding) -> Result<DistinguishedName, Error> {
+        let mut attributes = Vec::new();
+
+        for raw_attribute in split_unescaped(raw_name, ',')? {
+            if split_unescaped(&raw_attribute, '+')?.len() > 1 {
+                return Err(Error::InvalidSubject(format!(
+                    "multi-valued attribute \"{}\" is not supported",
+                    raw_attribute
+                )));
+            }
+
+            let (raw_type, raw_value) = match raw_attribute.find('=') {
+                Some(index) => (&raw_attribute[..index], &raw_attribute[index + 1..]),
+                None => {
+                    return Err(Error::InvalidSubject(format!(
+                        "\"{}\" is not of the form type=value",
+                        raw_attribute
+                    )))
+                }
+            };
+
+            let attribute = Attribute::from_short_name(raw_type.trim()).ok_or_else(|| {
+                Error::InvalidSubject(format!("unsupported attribute type \"{}\"", raw_type))
+            })?;
+
+            attributes.push((attribute, unescape(trim_unescaped(raw_value))?));
+        }
+
+        attributes.reverse();
+
+        let name = DistinguishedName {
+            attributes,
+            encoding,
+        };
+        name.validate()?;
+        Ok(name)
+    }
+
+    /// Build a name from individually provided attributes, ordered conventionally.
+    pub fn from_attributes(
+        mut attributes: Vec<(Attribute, String)>,
+        encoding: StringEncoding,
+    ) -> Result<DistinguishedName, Error> {
+        let order = [
+            Attribute::Country,
+            Attribute::State,
+            Attribute::Locality,
+            Attribute::Organization,
+            Attribute::OrganizationalUnit,
+            Attribute::CommonName,
+            Attribute::SerialNumber,
+            Attribute::EmailAddress,
+        ];
+        attributes.sort_by_key(|(attribute, _)| order.iter().position(|a| a == attribute));
+
+        let name = DistinguishedName {
+            attributes,
+            encoding,
+        };
+        name.validate()?;
+        Ok(name)
+    }
+
+    /// Take over an existing name, e.g. the subject of a certificate signing request, keeping the
+    /// order of its attributes but re-encoding them with the given string type.
+    pub fn from_x509_name(
+        x509_name: &X509NameRef,
+        encoding: StringEncoding,
+    ) -> Result<DistinguishedName, Error> {
+        let mut attributes = Vec::new();
+
+        for entry in x509_name.entries() {
+            let nid = entry.object().nid();
+            let attribute = Attribute::from_nid(nid).ok_or_else(|| {
+                Error::InvalidSubject(format!(
+                    "unsupported attribute type \"{}\"",
+                    nid.short_name().unwrap_or("unknown")
+                ))
+            })?;
+            let value = entry.data().as_utf8().map_err(|_| {
+                Error::InvalidSubject(format!(
+                    "the value of {} is not a valid string",
+                    attribute.short_name()
+                ))
+            })?;
+
+            attributes.push((attribute, value.to_string()));
+        }
+
+        let name = DistinguishedName {
+            attributes,
+            encoding,
+        };
+        name.validate()?;
+        Ok(name)
+    }
+
+    /// The common name made safe to base file names on.
+    pub fn file_stem(&self) -> Result<String, Error> {
+        let common_name = self.common_name().unwrap_or_default();
+
+        file_stem(common_name).ok_or_else(|| {
+            Error::InvalidSubject(format!(
+                "the common name \"{}\" cannot be used as a file name",
+                common_name
+            ))
+        })
+    }
+
+    pub fn common_name(&self) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attribute, _)| *attribute == Attribute::CommonName)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn to_x509_name(&self) -> Result<X509Name, ErrorStack> {
+        let mut x509_name = X509NameBuilder::new()?;
+
+        for (attribute, value) in &self.attributes {
+            x509_name.append_entry_by_nid_with_type(
+                attribute.nid(),
+                value,
+                attribute.asn1_type(self.encoding),
+            )?;
+        }
+
+        Ok(x509_name.build())
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if self.common_name().is_none() {
+            return Err(Error::InvalidSubject(
+                "a common name (CN) is required".to_string(),
+            ));
+        }
+
+        for (attribute, value) in &self.attributes {
+            let invalid = |reason: &str| {
+                Err(Error::InvalidSubject(format!(
+                    "{} \"{}\" {}",
+                    attribute.short_name(),
+                    value,
+                    reason
+                )))
+            };
+
+            if value.is_empty() {
+                return invalid("must not be empty");
+            }
+
+            if *attribute == Attribute::Country
+                && !(value.len() == 2 && value.chars().all(|c| c.is_ascii_uppercase()))
+            {
+                return invalid("is not a two-letter ISO 3166 country code");
+            }
+
+            if value.chars().count() > attribute.max_length() {
+                return invalid(&format!(
+                    "is longer than {} characters",
+                    attribute.max_length()
+                ));
+            }
+
+            let asn1_type = attribute.asn1_type(self.encoding);
+
+            if asn1_type == Asn1Type::PRINTABLESTRING
+                && !value.chars().all(is_printable_string_char)
+            {
+                return invalid("contains characters not allowed in a PrintableString");
+            }
+
+            if asn1_type == Asn1Type::IA5STRING && !value.is_ascii() {
+                return invalid("contains characters not allowed in an IA5String");
+            }
+
+            if *attribute == Attribute::EmailAddress && !value.contains('@') {
+                return invalid("is not an email address");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for DistinguishedName {
+    /// Formats the name as an RFC 4514 string.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rendered: Vec<String> = self
+            .attributes
+            .iter()
+            .rev()
+            .map(|(attribute, value)| format!("{}={}", attribute.short_name(), escape(value)))
+            .collect();
+
+        write!(f, "{}", rendered.join(","))
+    }
+}
+
+/// Render an existing name, e.g. the subject of a certificate that is copied unchanged, as an
+/// RFC 4514 string without requiring it to be one tch could build. Attribute types without a short
+/// name are given by OID and values that are not strings as # followed by their hex encoding.
+pub fn x509_name_to_string(x509_name: &X509NameRef) -> String {
+    let mut rendered: Vec<String> = x509_name
+        .entries()
+        .map(|entry| {
+            let object = entry.object();
+            let attribute_type = match object.nid() {
+                Nid::UNDEF => object.to_string(),
+                nid => nid
+                    .short_name()
+                    .map(str::to_string)
+                    .unwrap_or_else(|_| object.to_string()),
+            };
+            let value = match entry.data().as_utf8() {
+                Ok(value) => escape(&value),
+                Err(_) => entry
+                    .data()
+                    .as_slice()
+                    .iter()
+                    .fold("#".to_string(), |hex, byte| format!("{}{:02x}", hex, byte)),
+            };
+
+            format!("{}={}", attribute_type, value)
+        })
+        .collect();
+
+    rendered.reverse();
+    rendered.join(",")
+}
+
+/// The value of an existing name to base file names on: the common name, or else the most specific
+/// attribute with a string value that can name a file.
+pub fn x509_name_file_stem(x509_name: &X509NameRef) -> Option<String> {
+    let values: Vec<(Nid, String)> = x509_name
+        .entries()
+        .filter_map(|entry| {
+            let value = file_stem(&entry.data().as_utf8().ok()?)?;
+            Some((entry.object().nid(), value))
+        })
+        .collect();
+
+    values
+        .iter()
+        .find(|(nid, _)| *nid == Nid::COMMONNAME)
+        .or_else(|| values.last())
+        .map(|(_, value)| value.clone())
+}
+
+/// An attribute value made safe to base file names on, which may come from someone else's request
+/// or certificate. Path separators are replaced so that it stays one file name within the output
+/// directory, and values that would still name a directory are refused.
+fn file_stem(value: &str) -> Option<String> {
+    match value {
+        "" | "." | ".." => None,
+        _ => Some(value.replace(&['/', '\\'][..], "_")),
+    }
+}
+
+/// The PrintableString character set from X.680.
+fn is_printable_string_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || " '()+,-./:=?".contains(c)
+}
+
+/// Split on the separator wherever it is not escaped with a backslash, leaving escapes intact.
+fn split_unescaped(raw: &str, separator: char) -> Result<Vec<String>, Error> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut chars = raw.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                current.push(c);
+                match chars.next() {
+                    Some(escaped) => current.push(escaped),
+                    None => {
+                        return Err(Error::InvalidSubject(format!(
+                            "\"{}\" ends with an incomplete escape",
+                            raw
+                        )))
+                    }
+                }
+            }
+            c if c == separator => parts.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    parts.push(current);
+
+    Ok(parts)
+}
+
+/// Trim the spaces around an attribute value, except for a trailing space that is escaped.
+fn trim_unescaped(raw_value: &str) -> &str {
+    let mut trimmed = raw_value.trim_start();
+
+    while trimmed.ends_with(' ') {
+        let without_space = &trimmed[..trimmed.len() - 1];
+        let backslashes = without_space.len() - without_space.trim_end_matches('\\').len();
+
+        if backslashes & 1 == 1 {
+            break;
+        }
+        trimmed = without_space;
+    }
+
+    trimmed
+}
+
+/// Resolve the RFC 4514 escapes in an attribute value: a backslash followed by either a special
+/// character or two hex digits.
+fn unescape(raw_value: &str) -> Result<String, Error> {
+    if raw_value.starts_with('#') {
+        return Err(Error::InvalidSubject(format!(
+            "hex encoded value \"{}\" is not supported",
+            raw_value
+        )));
+    }
+
+    let mut bytes = Vec::new();
+    let mut chars = raw_value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buffer = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+
+        match chars.next() {
+            Some(high) if high.is_ascii_hexdigit() => {
+                let low = chars.next().filter(|low| low.is_ascii_hexdigit());
+                match low {
+                    Some(low) => bytes.push(
+                        u8::from_str_radix(&format!("{}{}", high, low), 16)
+                            .expect("two hex digits always fit in a byte"),
+                    ),
+                    None => {
+                        return Err(Error::InvalidSubject(format!(
+                            "\"{}\" contains an incomplete hex escape",
+                            raw_value
+                        )))
+                    }
+                }
+            }
+            Some(escaped) => {
+                let mut buffer = [0; 4];
+                bytes.extend_from_slice(escaped.encode_utf8(&mut buffer).as_bytes());
+            }
+            None => {
+                return Err(Error::InvalidSubject(format!(
+                    "\"{}\" ends with an incomplete escape",
+                    raw_value
+                )))
+            }
+        }
+    }
+
+    String::from_utf8(bytes).map_err(|_| {
+        Error::InvalidSubject(format!("\"{}\" does not escape valid UTF-8", raw_value))
+    })
+}
+
+/// Escape an attribute value for an RFC 4514 string.
+fn escape(value: &str) -> String {
+    let last_index = value.chars().count().saturating_sub(1);
+
+    value
+        .chars()
+        .enumerate()
+        .map(|(index, c)| {
+            let needs_escape = "\"+,;<>\\".contains(c)
+                || (index == 0 && (c == '#' || c == ' '))
+                || (index == last_index && c == ' ');
+            match needs_escape {
+                true => format!("\\{}", c),
+                false => c.to_string(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(raw_name: &str) -> Result<Vec<(Attribute, String)>, Error> {
+        DistinguishedName::parse(raw_name, StringEncoding::Utf8).map(|name| name.attributes)
+    }
+
+    fn common_name(raw_name: &str) -> String {
+        parse(raw_name).unwrap()[0].1.clone()
+    }
+
+    #[test]
+    fn parse_reverses_rfc_4514_order() {
+        assert_eq!(
+            parse("CN=device,O=Very,C=US").unwrap(),
+            vec![
+                (Attribute::Country, "US".to_string()),
+                (Attribute::Organization, "Very".to_string()),
+                (Attribute::CommonName, "device".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_resolves_escaped_specials() {
+        assert_eq!(common_name("CN=a\\,b"), "a,b");
+        assert_eq!(common_name("CN=a\\+b"), "a+b");
+        assert_eq!(common_name("CN=a\\\\b"), "a\\b");
+    }
+
+    #[test]
+    fn parse_resolves_hex_escapes() {
+        assert_eq!(common_name("CN=a\\2Cb"), "a,b");
+        assert_eq!(common_name("CN=caf\\C3\\A9"), "caf\u{e9}");
+    }
+
+    #[test]
+    fn parse_trims_unescaped_spaces() {
+        assert_eq!(
+            parse(" CN = device , O = Very ").unwrap(),
+            vec![
+                (Attribute::Organization, "Very".to_string()),
+                (Attribute::CommonName, "device".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_keeps_escaped_leading_and_trailing_spaces() {
+        assert_eq!(common_name("CN=\\ device"), " device");
+        assert_eq!(common_name("CN=device\\ "), "device ");
+        assert_eq!(common_name("CN=device\\  "), "device ");
+        assert_eq!(common_name("CN=device\\\\ "), "device\\");
+    }
+
+    #[test]
+    fn parse_rejects_multi_valued_attributes() {
+        assert!(parse("CN=a+O=b").is_err());
+    }
+
+    #[test]
+    fn parse_rejects_incomplete_escapes() {
+        assert!(parse("CN=a\\").is_err());
+        assert!(parse("CN=a\\2").is_err());
+        assert!(parse("CN=a\\2G").is_err());
+        assert!(parse("CN=a\\FF").is_err());
+    }
+
+    #[test]
+    fn parse_rejects_malformed_attributes() {
+        assert!(parse("CN").is_err());
+        assert!(parse("CN=a,XX=b").is_err());
+        assert!(parse("CN=#0403616263").is_err());
+        assert!(parse("O=Very").is_err());
+    }
+
+    #[test]
+    fn escape_escapes_specials_and_edge_spaces() {
+        assert_eq!(escape("a,b+c"), "a\\,b\\+c");
+        assert_eq!(escape(" a "), "\\ a\\ ");
+        assert_eq!(escape("#a#"), "\\#a#");
+        assert_eq!(escape(""), "");
+    }
+
+    #[test]
+    fn display_round_trips_through_parse() {
+        let raw_name = "CN=\\ a\\,b\\ ,O=x\\+y";
+        let name = DistinguishedName::parse(raw_name, StringEncoding::Utf8).unwrap();
+
+        assert_eq!(name.to_string(), raw_name);
+    }
+
+    #[test]
+    fn x509_name_without_common_name_renders_and_names_files() {
+        let mut builder = X509NameBuilder::new().unwrap();
+        builder.append_entry_by_text("O", "Very").unwrap();
+        builder.append_entry_by_text("OU", "Roots, Inc").unwrap();
+        builder.append_entry_by_text("1.2.3.4", "x/y").unwrap();
+        let x509_name = builder.build();
+
+        assert_eq!(
+            x509_name_to_string(&x509_name),
+            "1.2.3.4=x/y,OU=Roots\\, Inc,O=Very"
+        );
+        assert_eq!(x509_name_file_stem(&x509_name).unwrap(), "x_y");
+    }
+
+    #[test]
+    fn file_stem_stays_within_the_output_directory() {
+        let name = DistinguishedName::parse("CN=../..\\\\x/pwned", StringEncoding::Utf8).unwrap();
+
+        assert_eq!(name.file_stem().unwrap(), ".._.._x_pwned");
+
+        for common_name in &[".", ".."] {
+            let name = DistinguishedName::from_attributes(
+                vec![(Attribute::CommonName, common_name.to_string())],
+                StringEncoding::Utf8,
+            )
+            .unwrap();
+
+            assert!(name.file_stem().is_err());
+        }
+
+        let mut builder = X509NameBuilder::new().unwrap();
+        builder.append_entry_by_text("O", "Very").unwrap();
+        builder.append_entry_by_text("CN", "..").unwrap();
+
+        assert_eq!(x509_name_file_stem(&builder.build()).unwrap(), "Very");
+    }
+
+    #[test]
+    fn x509_name_file_stem_prefers_the_common_name() {
+        let name = DistinguishedName::parse("OU=b,CN=a,O=c", StringEncoding::Utf8).unwrap();
+
+        assert_eq!(
+            x509_name_file_stem(&name.to_x509_name().unwrap()).unwrap(),
+            "a"
+        );
+    }
+}
@@ -0,0 +1,316 @@
+/*
+Name constraints: the permitted and excluded subtrees that restrict which names a CA may certify, and
+the check of a certificate's names against the constraints of its signer.
+*/
+use super::ffi;
+use super::san::{self, SubjectAltName};
+use crate::Error;
+use openssl::error::ErrorStack;
+use openssl::nid::Nid;
+use openssl::x509::{X509Extension, X509NameRef, X509Ref, X509v3Context, X509};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Subtree {
+    /// A domain and all of its subdomains.
+    Dns(String),
+    /// A mailbox, all mailboxes on a host, or, with a leading dot, all mailboxes in a domain.
+    Email(String),
+    /// An address range in CIDR notation.
+    Ip(IpAddr, u8),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NameConstraints {
+    pub permitted: Vec<Subtree>,
+    pub excluded: Vec<Subtree>,
+}
+
+impl Subtree {
+    pub fn dns(raw_domain: &str) -> Result<Subtree, Error> {
+        let domain = raw_domain.strip_prefix('.').unwrap_or(raw_domain);
+
+        if domain.starts_with('*') {
+            return Err(Error::InvalidNameConstraint(format!(
+                "DNS subtree \"{}\" must not contain a wildcard, it already includes all subdomains",
+                raw_domain
+            )));
+        }
+
+        Ok(Subtree::Dns(san::normalize_dns_name(domain)?))
+    }
+
+    pub fn email(raw_email: &str) -> Result<Subtree, Error> {
+        if raw_email.contains('@') {
+            return match SubjectAltName::email(raw_email)? {
+                SubjectAltName::Email(email) => Ok(Subtree::Email(email)),
+                _ => unreachable!(),
+            };
+        }
+
+        match raw_email.strip_prefix('.') {
+            Some(domain) => Ok(Subtree::Email(format!(
+                ".{}",
+                san::normalize_dns_name(domain)?
+            ))),
+            None => Ok(Subtree::Email(san::normalize_dns_name(raw_email)?)),
+        }
+    }
+
+    pub fn ip(raw_range: &str) -> Result<Subtree, Error> {
+        let invalid = |reason: &str| {
+            Err(Error::InvalidNameConstraint(format!(
+                "IP subtree \"{}\" {}",
+                raw_range, reason
+            )))
+        };
+
+        let (raw_address, raw_prefix_length) = match raw_range.find('/') {
+            Some(index) => (&raw_range[..index], &raw_range[index + 1..]),
+            None => return invalid("is not in CIDR notation, e.g. 10.0.0.0/8"),
+        };
+
+        let address: IpAddr = match raw_address.parse() {
+            Ok(address) => address,
+            Err(_) => return invalid("does not start with an IP address"),
+        };
+
+        let max_prefix_length = match address {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+
+        let prefix_length = match raw_prefix_length.parse::<u8>() {
+            Ok(prefix_length) if prefix_length <= max_prefix_length => prefix_length,
+            _ => return invalid("has an invalid prefix length"),
+        };
+
+        let subtree = Subtree::Ip(address, prefix_length);
+
+        if subtree.network_address() != address {
+            return invalid("has bits set after the prefix");
+        }
+
+        Ok(subtree)
+    }
+
+    /// The netmask of an IP subtree, which is how the extension encodes the prefix length.
+    fn netmask(&self) -> Option<IpAddr> {
+        match self {
+            Subtree::Ip(IpAddr::V4(_), prefix_length) => Some(IpAddr::V4(Ipv4Addr::from(
+                u32::MAX
+                    .checked_shl(32 - u32::from(*prefix_length))
+                    .unwrap_or(0),
+            ))),
+            Subtree::Ip(IpAddr::V6(_), prefix_length) => Some(IpAddr::V6(Ipv6Addr::from(
+                u128::MAX
+                    .checked_shl(128 - u32::from(*prefix_length))
+                    .unwrap_or(0),
+            ))),
+            _ => None,
+        }
+    }
+
+    fn network_address(&self) -> IpAddr {
+        match (self, self.netmask()) {
+            (Subtree::Ip(IpAddr::V4(address), _), Some(IpAddr::V4(netmask))) => {
+                IpAddr::V4(Ipv4Addr::from(u32::from(*address) & u32::from(netmask)))
+            }
+            (Subtree::Ip(IpAddr::V6(address), _), Some(IpAddr::V6(netmask))) => {
+                IpAddr::V6(Ipv6Addr::from(u128::from(*address) & u128::from(netmask)))
+            }
+            _ => panic!("only IP subtrees have a network address"),
+        }
+    }
+
+    /// The subtree in the general name syntax of OpenSSL's extension configuration.
+    fn to_conf(&self) -> String {
+        match self {
+            Subtree::Dns(domain) => format!("DNS:{}", domain),
+            Subtree::Email(email) => format!("email:{}", email),
+            Subtree::Ip(address, _) => format!("IP:{}/{}", address, self.netmask().unwrap()),
+        }
+    }
+}
+
+impl fmt::Display for Subtree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Subtree::Dns(domain) => write!(f, "DNS:{}", domain),
+            Subtree::Email(email) => write!(f, "email:{}", email),
+            Subtree::Ip(address, prefix_length) => write!(f, "IP:{}/{}", address, prefix_length),
+        }
+    }
+}
+
+impl NameConstraints {
+    pub fn is_empty(&self) -> bool {
+        self.permitted.is_empty() && self.excluded.is_empty()
+    }
+
+    /// Build the critical NameConstraints extension, as RFC 5280 requires it to be marked.
+    pub fn to_extension(&self, context: &X509v3Context) -> Result<X509Extension, ErrorStack> {
+        let mut conf = vec!["critical".to_string()];
+
+        for subtree in &self.permitted {
+            conf.push(format!("permitted;{}", subtree.to_conf()));
+        }
+
+        for subtree in &self.excluded {
+            conf.push(format!("excluded;{}", subtree.to_conf()));
+        }
+
+        X509Extension::new_nid(None, Some(context), Nid::NAME_CONSTRAINTS, &conf.join(","))
+    }
+}
+
+/// Refuse to certify a subject or subject alternative name that the signer certificate's name
+/// constraints do not allow. Each name is checked on its own so that the error can point at it.
+pub fn check(
+    signer_certificate: &X509Ref,
+    subject: &X509NameRef,
+    subject_alt_names: &[SubjectAltName],
+) -> Result<(), Error> {
+    let mut subject_only = X509::builder()?;
+    subject_only.set_subject_name(subject)?;
+
+    ffi::check_name_constraints(signer_certificate, &subject_only.build()).map_err(|reason| {
+        Error::NameConstraintViolation {
+            name: "the subject".to_string(),
+            reason: reason.to_string(),
+        }
+    })?;
+
+    for name in subject_alt_names {
+        let mut name_only = X509::builder()?;
+        let subject_alt_name = san::to_extension(
+            std::slice::from_ref(name),
+            &name_only.x509v3_context(None, None),
+        )?;
+        name_only.append_extension(subject_alt_name)?;
+
+        ffi::check_name_constraints(signer_certificate, &name_only.build()).map_err(|reason| {
+            Error::NameConstraintViolation {
+                name: name.to_string(),
+                reason: reason.to_string(),
+            }
+        })?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::x509::X509Name;
+
+    fn signer(permitted: &[Subtree], excluded: &[Subtree]) -> X509 {
+        let name_constraints = NameConstraints {
+            permitted: permitted.to_vec(),
+            excluded: excluded.to_vec(),
+        };
+
+        let mut signer = X509::builder().unwrap();
+        let extension = name_constraints
+            .to_extension(&signer.x509v3_context(None, None))
+            .unwrap();
+        signer.append_extension(extension).unwrap();
+        signer.build()
+    }
+
+    fn subject(common_name: &str) -> X509Name {
+        let mut subject = X509Name::builder().unwrap();
+        subject.append_entry_by_text("CN", common_name).unwrap();
+        subject.build()
+    }
+
+    fn check_dns(signer: &X509, name: &str) -> Result<(), Error> {
+        check(
+            signer,
+            &subject("device"),
+            &[SubjectAltName::dns(name).unwrap()],
+        )
+    }
+
+    #[test]
+    fn dns_subtree_matches_whole_labels_only() {
+        let signer = signer(&[Subtree::dns("team.example.com").unwrap()], &[]);
+
+        assert!(check_dns(&signer, "team.example.com").is_ok());
+        assert!(check_dns(&signer, "device.team.example.com").is_ok());
+        assert!(check_dns(&signer, "evilteam.example.com").is_err());
+        assert!(check_dns(&signer, "team.example.com.evil.com").is_err());
+    }
+
+    #[test]
+    fn ip_subtree_is_parsed_from_cidr_notation() {
+        assert_eq!(
+            Subtree::ip("10.0.0.0/8").unwrap(),
+            Subtree::Ip(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 0)), 8)
+        );
+        assert_eq!(
+            Subtree::ip("2001:db8::/32").unwrap().to_string(),
+            "IP:2001:db8::/32"
+        );
+        assert_eq!(
+            Subtree::ip("192.168.1.0/24").unwrap().to_conf(),
+            "IP:192.168.1.0/255.255.255.0"
+        );
+        assert!(Subtree::ip("10.0.0.0").is_err());
+        assert!(Subtree::ip("10.0.0.1/8").is_err());
+        assert!(Subtree::ip("10.0.0.0/33").is_err());
+        assert!(Subtree::ip("2001:db8::/129").is_err());
+        assert!(Subtree::ip("example.com/8").is_err());
+    }
+
+    #[test]
+    fn excluded_subtrees_take_precedence() {
+        let signer = signer(
+            &[
+                Subtree::dns("example.com").unwrap(),
+                Subtree::ip("10.0.0.0/8").unwrap(),
+            ],
+            &[
+                Subtree::dns("internal.example.com").unwrap(),
+                Subtree::ip("10.1.0.0/16").unwrap(),
+            ],
+        );
+        let check_ip = |ip| {
+            check(
+                &signer,
+                &subject("device"),
+                &[SubjectAltName::ip(ip).unwrap()],
+            )
+        };
+
+        assert!(check_dns(&signer, "device.example.com").is_ok());
+        assert!(check_dns(&signer, "device.internal.example.com").is_err());
+        assert!(check_ip("10.2.0.1").is_ok());
+        assert!(check_ip("10.1.0.1").is_err());
+    }
+
+    #[test]
+    fn check_names_the_offending_subject_or_subject_alt_name() {
+        let signer = signer(&[Subtree::dns("example.com").unwrap()], &[]);
+        let names = vec![
+            SubjectAltName::dns("a.example.com").unwrap(),
+            SubjectAltName::dns("b.example.org").unwrap(),
+        ];
+
+        assert!(check(&signer, &subject("device.example.com"), &names[..1]).is_ok());
+
+        match check(&signer, &subject("device.example.org"), &names[..1]) {
+            Err(Error::NameConstraintViolation { name, .. }) => assert_eq!(name, "the subject"),
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        match check(&signer, &subject("device.example.com"), &names) {
+            Err(Error::NameConstraintViolation { name, .. }) => {
+                assert_eq!(name, "DNS:b.example.org")
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+}
@@ -0,0 +1,173 @@
+/*
+Certificate profiles: the KeyUsage and ExtendedKeyUsage extensions of end-entity certificates, chosen
+by intended use and adjusted to what the subject's key type can actually do.
+*/
+use openssl::error::ErrorStack;
+use openssl::pkey::{HasPublic, Id, PKeyRef};
+use openssl::x509::extension::{ExtendedKeyUsage, KeyUsage};
+use openssl::x509::X509Extension;
+use strum_macros::Display;
+
+#[derive(Display, Debug, Clone, Copy)]
+pub enum Profile {
+    /// TLS client authentication.
+    #[strum(to_string = "client")]
+    Client,
+    #[strum(to_string = "code-signing")]
+    CodeSigning,
+    /// S/MIME email protection.
+    #[strum(to_string = "email")]
+    Email,
+    /// TLS server and client authentication, e.g. for devices that both accept and open connections.
+    #[strum(to_string = "mtls")]
+    Mtls,
+    /// Delegated OCSP response signing.
+    #[strum(to_string = "ocsp-signing")]
+    OcspSigning,
+    /// TLS server authentication.
+    #[strum(to_string = "server")]
+    Server,
+    /// RFC 3161 time stamping.
+    #[strum(to_string = "time-stamping")]
+    TimeStamping,
+}
+
+// KeyUsage bits in the first octet of the bit string.
+const DIGITAL_SIGNATURE: u8 = 0x80;
+const NON_REPUDIATION: u8 = 0x40;
+const KEY_ENCIPHERMENT: u8 = 0x20;
+const DATA_ENCIPHERMENT: u8 = 0x10;
+const KEY_AGREEMENT: u8 = 0x08;
+const KEY_CERT_SIGN: u8 = 0x04;
+const CRL_SIGN: u8 = 0x02;
+
+/// What the subject's key can be used for beyond signing.
+enum KeyCapability {
+    /// RSA keys can encrypt, which TLS 1.2 RSA key exchange and S/MIME key transport rely on.
+    Encipherment,
+    /// EC keys can take part in ECDH, which S/MIME uses to agree on content encryption keys.
+    Agreement,
+    /// RSASSA-PSS and EdDSA keys can only sign.
+    SigningOnly,
+}
+
+impl Profile {
+    /// Build the KeyUsage and ExtendedKeyUsage extensions of the profile for the given subject key.
+    pub fn extensions<T: HasPublic>(
+        self,
+        subject_key: &PKeyRef<T>,
+    ) -> Result<Vec<X509Extension>, ErrorStack> {
+        let key_capability = key_capability(subject_key);
+
+        let mut key_usage = KeyUsage::new();
+        key_usage.critical().digital_signature();
+
+        match (self, key_capability) {
+            (Profile::Client, KeyCapability::Encipherment)
+            | (Profile::Mtls, KeyCapability::Encipherment)
+            | (Profile::Server, KeyCapability::Encipherment) => {
+                key_usage.key_encipherment();
+            }
+            (Profile::Email, KeyCapability::Encipherment) => {
+                key_usage.non_repudiation().key_encipherment();
+            }
+            (Profile::Email, KeyCapability::Agreement) => {
+                key_usage.non_repudiation().key_agreement();
+            }
+            (Profile::Email, KeyCapability::SigningOnly) | (Profile::TimeStamping, _) => {
+                key_usage.non_repudiation();
+            }
+            _ => (),
+        }
+
+        let mut extended_key_usage = ExtendedKeyUsage::new();
+
+        match self {
+            Profile::Client => extended_key_usage.client_auth(),
+            Profile::CodeSigning => extended_key_usage.code_signing(),
+            Profile::Email => extended_key_usage.email_protection(),
+            Profile::Mtls => extended_key_usage.server_auth().client_auth(),
+            Profile::OcspSigning => extended_key_usage.other("OCSPSigning"),
+            Profile::Server => extended_key_usage.server_auth(),
+            // RFC 3161 requires timeStamping to be the only, critical, extended key usage.
+            Profile::TimeStamping => extended_key_usage.critical().time_stamping(),
+        };
+
+        Ok(vec![key_usage.build()?, extended_key_usage.build()?])
+    }
+}
+
+fn key_capability<T: HasPublic>(subject_key: &PKeyRef<T>) -> KeyCapability {
+    match subject_key.id() {
+        Id::RSA => KeyCapability::Encipherment,
+        Id::EC => KeyCapability::Agreement,
+        _ => KeyCapability::SigningOnly,
+    }
+}
+
+/// Adjust the DER value of a KeyUsage extension made for a key of another type to what the subject
+/// key can do: any of key encipherment, data encipherment and key agreement is replaced by whichever
+/// the key supports, and the restrictions of key agreement are dropped. None if the value is not a
+/// KeyUsage bit string.
+pub fn rekeyed_key_usage<T: HasPublic>(value: &[u8], subject_key: &PKeyRef<T>) -> Option<Vec<u8>> {
+    // The BIT STRING tag, a short form length, the number of unused bits and the bits.
+    let first_octet = match value {
+        [0x03, length, unused_bits, bits @ ..]
+            if usize::from(*length) == bits.len() + 1 && *unused_bits < 8 =>
+        {
+            bits.first().copied().unwrap_or(0)
+        }
+        _ => return None,
+    };
+
+    let kept = first_octet & (DIGITAL_SIGNATURE | NON_REPUDIATION | KEY_CERT_SIGN | CRL_SIGN);
+    let key_establishment =
+        first_octet & (KEY_ENCIPHERMENT | DATA_ENCIPHERMENT | KEY_AGREEMENT) != 0;
+
+    let bits = match (key_establishment, key_capability(subject_key)) {
+        (true, KeyCapability::Encipherment) => kept | KEY_ENCIPHERMENT,
+        (true, KeyCapability::Agreement) => kept | KEY_AGREEMENT,
+        _ => kept,
+    };
+
+    // DER leaves out trailing zero bits.
+    Some(match bits {
+        0 => vec![0x03, 0x01, 0x00],
+        _ => vec![0x03, 0x02, bits.trailing_zeros() as u8, bits],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::nid::Nid;
+    use openssl::pkey::PKey;
+
+    #[test]
+    fn rekeyed_key_usage_swaps_key_establishment_for_the_key_type() {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let ec_key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let ed25519_key = PKey::generate_ed25519().unwrap();
+
+        // digitalSignature and keyEncipherment, as for an RSA server certificate.
+        let rsa_server = [0x03, 0x02, 0x05, 0xa0];
+
+        assert_eq!(
+            rekeyed_key_usage(&rsa_server, &ec_key).unwrap(),
+            vec![0x03, 0x02, 0x03, 0x88]
+        );
+        assert_eq!(
+            rekeyed_key_usage(&rsa_server, &ed25519_key).unwrap(),
+            vec![0x03, 0x02, 0x07, 0x80]
+        );
+    }
+
+    #[test]
+    fn rekeyed_key_usage_rejects_other_values() {
+        let ed25519_key = PKey::generate_ed25519().unwrap();
+
+        assert!(rekeyed_key_usage(&[0x04, 0x02, 0x05, 0xa0], &ed25519_key).is_none());
+        assert!(rekeyed_key_usage(&[0x03, 0x03, 0x05, 0xa0], &ed25519_key).is_none());
+    }
+}
@@ -0,0 +1,95 @@
+/*
+Certificate signing requests created elsewhere: loading them, verifying their self-signature, and
+reading the subject, subject alternative names and extensions they request.
+*/
+use super::extensions::CustomExtension;
+use super::ffi;
+use super::name::{DistinguishedName, StringEncoding};
+use super::san::{self, SubjectAltName};
+use super::SUBJECT_ALT_NAME_OID;
+use crate::Error;
+use openssl::pkey::{PKey, Public};
+use openssl::x509::{X509Req, X509};
+use std::fs;
+use std::path::Path;
+
+pub struct CertificateRequest {
+    request: X509Req,
+    public_key: PKey<Public>,
+}
+
+impl CertificateRequest {
+    /// Load a PEM or DER encoded request, refusing it unless it is signed by the key it contains,
+    /// which proves that the requester holds the private key.
+    pub fn load(request_path: &Path) -> Result<CertificateRequest, Error> {
+        let request_bytes: Vec<u8> = fs::read(request_path)?;
+
+        let request = match request_bytes.starts_with(b"-----BEGIN") {
+            true => X509Req::from_pem(&request_bytes)?,
+            false => X509Req::from_der(&request_bytes)?,
+        };
+
+        let public_key = request.public_key()?;
+
+        // A signature that cannot be checked at all, e.g. a malformed one, is reported as invalid too.
+        if !request.verify(&public_key).unwrap_or(false) {
+            return Err(Error::RequestSignatureInvalid(request_path.to_path_buf()));
+        }
+
+        Ok(CertificateRequest {
+            request,
+            public_key,
+        })
+    }
+
+    pub fn public_key(&self) -> &PKey<Public> {
+        &self.public_key
+    }
+
+    /// The requested subject, encoded with the given string type like a subject given on the
+    /// command line.
+    pub fn subject(&self, encoding: StringEncoding) -> Result<DistinguishedName, Error> {
+        DistinguishedName::from_x509_name(self.request.subject_name(), encoding)
+    }
+
+    /// All requested extensions, including the ones tch sets itself.
+    pub fn extensions(&self) -> Result<Vec<CustomExtension>, Error> {
+        let extensions = match self.request.extensions() {
+            Ok(extensions) => extensions,
+            // OpenSSL reports a request without the extension request attribute as an error.
+            Err(_) => return Ok(Vec::new()),
+        };
+
+        Ok(extensions
+            .iter()
+            .map(CustomExtension::from_extension)
+            .collect())
+    }
+
+    /// The requested subject alternative names that are valid and of a supported type, and the
+    /// number of the others.
+    pub fn supported_subject_alt_names(&self) -> Result<(Vec<SubjectAltName>, usize), Error> {
+        let names_only = self.names_only()?;
+
+        Ok(san::supported_from_certificate(&names_only))
+    }
+
+    /// A certificate holding only the requested subject alternative names, through which they are
+    /// read back.
+    fn names_only(&self) -> Result<X509, Error> {
+        let mut names_only = X509::builder()?;
+
+        // OpenSSL reports a request without the extension request attribute as an error.
+        if let Ok(extensions) = self.request.extensions() {
+            for extension in &extensions {
+                let (oid, _, _) = ffi::extension_parts(extension);
+
+                if oid == SUBJECT_ALT_NAME_OID {
+                    names_only.append_extension2(extension)?;
+                }
+            }
+        }
+
+        Ok(names_only.build())
+    }
+}
@@ -0,0 +1,367 @@
+/*
+Subject alternative names: validation and normalization of the names requested on the command line.
+Internationalized domain names are converted to their punycode (A-label) form.
+*/
+use crate::Error;
+use openssl::error::ErrorStack;
+use openssl::x509::extension::SubjectAlternativeName;
+use openssl::x509::{X509Extension, X509Ref, X509v3Context};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubjectAltName {
+    Dns(String),
+    Email(String),
+    Ip(IpAddr),
+    Uri(String),
+}
+
+impl SubjectAltName {
+    pub fn dns(raw_name: &str) -> Result<SubjectAltName, Error> {
+        Ok(SubjectAltName::Dns(normalize_dns_name(raw_name)?))
+    }
+
+    pub fn email(raw_email: &str) -> Result<SubjectAltName, Error> {
+        let invalid = |reason: &str| {
+            Err(Error::InvalidSubjectAltName(format!(
+                "email address \"{}\" {}",
+                raw_email, reason
+            )))
+        };
+
+        let (local_part, domain) = match raw_email.rfind('@') {
+            Some(index) => (&raw_email[..index], &raw_email[index + 1..]),
+            None => return invalid("is missing an @"),
+        };
+
+        if local_part.is_empty() {
+            return invalid("has an empty local part");
+        }
+
+        // An rfc822Name is an IA5String, internationalized local parts need SmtpUTF8Mailbox.
+        if !local_part.is_ascii() || local_part.chars().any(|c| c.is_ascii_whitespace()) {
+            return invalid("has a local part that is not plain ASCII");
+        }
+
+        if domain.starts_with("*.") {
+            return invalid("must not contain a wildcard");
+        }
+
+        Ok(SubjectAltName::Email(format!(
+            "{}@{}",
+            local_part,
+            normalize_dns_name(domain)?
+        )))
+    }
+
+    pub fn ip(raw_ip: &str) -> Result<SubjectAltName, Error> {
+        raw_ip.parse().map(SubjectAltName::Ip).map_err(|_| {
+            Error::InvalidSubjectAltName(format!("\"{}\" is not an IP address", raw_ip))
+        })
+    }
+
+    pub fn uri(raw_uri: &str) -> Result<SubjectAltName, Error> {
+        let scheme_length = raw_uri.find(':').unwrap_or(0);
+        let scheme = &raw_uri[..scheme_length];
+
+        let has_scheme = matches!(scheme.chars().next(), Some(c) if c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+
+        if !has_scheme || !raw_uri.is_ascii() || raw_uri.chars().any(|c| c.is_ascii_whitespace()) {
+            return Err(Error::InvalidSubjectAltName(format!(
+                "\"{}\" is not an absolute ASCII URI",
+                raw_uri
+            )));
+        }
+
+        Ok(SubjectAltName::Uri(raw_uri.to_string()))
+    }
+
+    /// Parse a name in the form it is displayed, i.e. prefixed with DNS:, IP:, email: or URI:.
+    pub fn parse(raw_name: &str) -> Result<SubjectAltName, Error> {
+        let (name_type, value) = match raw_name.find(':') {
+            Some(index) => (&raw_name[..index], &raw_name[index + 1..]),
+            None => ("", raw_name),
+        };
+
+        match name_type.to_ascii_lowercase().as_str() {
+            "dns" => SubjectAltName::dns(value),
+            "ip" => SubjectAltName::ip(value),
+            "email" => SubjectAltName::email(value),
+            "uri" => SubjectAltName::uri(value),
+            _ => Err(Error::InvalidSubjectAltName(format!(
+                "\"{}\" does not start with DNS:, IP:, email: or URI:",
+                raw_name
+            ))),
+        }
+    }
+
+    /// The name to add for a common name that looks like a fully qualified hostname, if any.
+    pub fn from_common_name(common_name: &str) -> Option<SubjectAltName> {
+        if !common_name.contains('.') || common_name.parse::<IpAddr>().is_ok() {
+            return None;
+        }
+
+        SubjectAltName::dns(common_name).ok()
+    }
+}
+
+/// Build a SubjectAlternativeName extension holding the given names.
+pub fn to_extension(
+    names: &[SubjectAltName],
+    context: &X509v3Context,
+) -> Result<X509Extension, ErrorStack> {
+    let mut subject_alt_name = SubjectAlternativeName::new();
+
+    for name in names {
+        match name {
+            SubjectAltName::Dns(dns_name) => subject_alt_name.dns(dns_name),
+            SubjectAltName::Email(email) => subject_alt_name.email(email),
+            SubjectAltName::Ip(ip) => subject_alt_name.ip(&ip.to_string()),
+            SubjectAltName::Uri(uri) => subject_alt_name.uri(uri),
+        };
+    }
+
+    subject_alt_name.build(context)
+}
+
+/// The subject alternative names of an existing certificate that are valid and of a supported
+/// type, and the number of the others.
+pub fn supported_from_certificate(certificate: &X509Ref) -> (Vec<SubjectAltName>, usize) {
+    let mut supported = Vec::new();
+    let mut unsupported = 0;
+
+    for name in each_from_certificate(certificate) {
+        match name {
+            Ok(name) => supported.push(name),
+            Err(_) => unsupported += 1,
+        }
+    }
+
+    (supported, unsupported)
+}
+
+/// The subject alternative names of an existing certificate, each validated on its own so that
+/// names of unsupported types can be skipped.
+pub fn each_from_certificate(certificate: &X509Ref) -> Vec<Result<SubjectAltName, Error>> {
+    let general_names = match certificate.subject_alt_names() {
+        Some(general_names) => general_names,
+        None => return Vec::new(),
+    };
+
+    general_names
+        .iter()
+        .map(|general_name| {
+            if let Some(dns_name) = general_name.dnsname() {
+                SubjectAltName::dns(dns_name)
+            } else if let Some(email) = general_name.email() {
+                SubjectAltName::email(email)
+            } else if let Some(uri) = general_name.uri() {
+                SubjectAltName::uri(uri)
+            } else if let Some(ip) = general_name.ipaddress().and_then(to_ip_address) {
+                Ok(SubjectAltName::Ip(ip))
+            } else {
+                Err(Error::InvalidSubjectAltName(
+                    "a name of an unsupported type is included".to_string(),
+                ))
+            }
+        })
+        .collect()
+}
+
+fn to_ip_address(octets: &[u8]) -> Option<IpAddr> {
+    match octets.len() {
+        4 => {
+            let mut address = [0; 4];
+            address.copy_from_slice(octets);
+            Some(IpAddr::V4(Ipv4Addr::from(address)))
+        }
+        16 => {
+            let mut address = [0; 16];
+            address.copy_from_slice(octets);
+            Some(IpAddr::V6(Ipv6Addr::from(address)))
+        }
+        _ => None,
+    }
+}
+
+impl fmt::Display for SubjectAltName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SubjectAltName::Dns(name) => write!(f, "DNS:{}", name),
+            SubjectAltName::Email(email) => write!(f, "email:{}", email),
+            SubjectAltName::Ip(ip) => write!(f, "IP:{}", ip),
+            SubjectAltName::Uri(uri) => write!(f, "URI:{}", uri),
+        }
+    }
+}
+
+/// Validate a DNS name and convert it to its lowercase A-label form. A wildcard is only allowed as
+/// the entire leftmost label and must be followed by at least two labels, e.g. `*.example.com`.
+pub(super) fn normalize_dns_name(raw_name: &str) -> Result<String, Error> {
+    let invalid = |reason: &str| {
+        Err(Error::InvalidSubjectAltName(format!(
+            "DNS name \"{}\" {}",
+            raw_name, reason
+        )))
+    };
+
+    let name = raw_name.strip_suffix('.').unwrap_or(raw_name);
+
+    let (wildcard, name) = match name.strip_prefix("*.") {
+        Some(rest) => (true, rest),
+        None => (false, name),
+    };
+
+    if name.contains('*') {
+        return invalid("may only use a wildcard as the entire leftmost label");
+    }
+
+    let ascii_name = match idna::domain_to_ascii(name) {
+        Ok(ascii_name) => ascii_name,
+        Err(_) => return invalid("is not a valid internationalized domain name"),
+    };
+
+    let labels: Vec<&str> = ascii_name.split('.').collect();
+
+    if wildcard && labels.len() < 2 {
+        return invalid("must have at least two labels after the wildcard");
+    }
+
+    for label in &labels {
+        if label.is_empty() || label.len() > 63 {
+            return invalid("has a label that is empty or longer than 63 characters");
+        }
+
+        if label.starts_with('-') || label.ends_with('-') {
+            return invalid("has a label that starts or ends with a hyphen");
+        }
+
+        if !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return invalid("has a label with characters other than letters, digits and hyphens");
+        }
+    }
+
+    let ascii_name = match wildcard {
+        true => format!("*.{}", ascii_name),
+        false => ascii_name,
+    };
+
+    if ascii_name.len() > 253 {
+        return invalid("is longer than 253 characters");
+    }
+
+    Ok(ascii_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dns(raw_name: &str) -> String {
+        SubjectAltName::dns(raw_name).unwrap().to_string()
+    }
+
+    #[test]
+    fn dns_converts_internationalized_names_to_punycode() {
+        assert_eq!(dns("bücher.example"), "DNS:xn--bcher-kva.example");
+        assert_eq!(dns("Device-1.Example.COM."), "DNS:device-1.example.com");
+    }
+
+    #[test]
+    fn dns_only_accepts_a_whole_leftmost_wildcard_label() {
+        assert_eq!(dns("*.example.com"), "DNS:*.example.com");
+        assert_eq!(dns("*.bücher.example"), "DNS:*.xn--bcher-kva.example");
+        assert!(SubjectAltName::dns("*.com").is_err());
+        assert!(SubjectAltName::dns("dev*.example.com").is_err());
+        assert!(SubjectAltName::dns("a.*.example.com").is_err());
+    }
+
+    #[test]
+    fn dns_refuses_invalid_labels() {
+        assert!(SubjectAltName::dns("a..example.com").is_err());
+        assert!(SubjectAltName::dns("-a.example.com").is_err());
+        assert!(SubjectAltName::dns("a-.example.com").is_err());
+        assert!(SubjectAltName::dns("a_b.example.com").is_err());
+        assert!(SubjectAltName::dns(&format!("{}.example.com", "a".repeat(64))).is_err());
+        assert!(SubjectAltName::dns(&format!("{}.com", "a.".repeat(126))).is_err());
+    }
+
+    #[test]
+    fn ip_accepts_ipv4_and_ipv6() {
+        assert_eq!(
+            SubjectAltName::ip("10.0.0.1").unwrap(),
+            SubjectAltName::Ip(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)))
+        );
+        assert_eq!(
+            SubjectAltName::ip("2001:DB8::1").unwrap().to_string(),
+            "IP:2001:db8::1"
+        );
+        assert!(SubjectAltName::ip("10.0.0.256").is_err());
+        assert!(SubjectAltName::ip("example.com").is_err());
+    }
+
+    #[test]
+    fn email_normalizes_the_domain_only() {
+        assert_eq!(
+            SubjectAltName::email("Ops@Bücher.example")
+                .unwrap()
+                .to_string(),
+            "email:Ops@xn--bcher-kva.example"
+        );
+        assert!(SubjectAltName::email("ops.example.com").is_err());
+        assert!(SubjectAltName::email("@example.com").is_err());
+        assert!(SubjectAltName::email("öps@example.com").is_err());
+        assert!(SubjectAltName::email("ops@*.example.com").is_err());
+    }
+
+    #[test]
+    fn uri_must_be_absolute_ascii() {
+        assert_eq!(
+            SubjectAltName::uri("spiffe://example.com/device-1")
+                .unwrap()
+                .to_string(),
+            "URI:spiffe://example.com/device-1"
+        );
+        assert!(SubjectAltName::uri("//example.com/device-1").is_err());
+        assert!(SubjectAltName::uri("1https://example.com").is_err());
+        assert!(SubjectAltName::uri("https://example.com/a b").is_err());
+        assert!(SubjectAltName::uri("https://bücher.example").is_err());
+    }
+
+    #[test]
+    fn parse_reads_names_as_they_are_displayed() {
+        for raw_name in &[
+            "DNS:device-1.example.com",
+            "IP:2001:db8::1",
+            "email:ops@example.com",
+            "URI:https://example.com",
+        ] {
+            assert_eq!(
+                SubjectAltName::parse(raw_name).unwrap().to_string(),
+                *raw_name
+            );
+        }
+
+        assert_eq!(
+            SubjectAltName::parse("dns:Device-1.example.com").unwrap(),
+            SubjectAltName::Dns("device-1.example.com".to_string())
+        );
+        assert!(SubjectAltName::parse("device-1.example.com").is_err());
+        assert!(SubjectAltName::parse("otherName:1.2.3.4").is_err());
+    }
+
+    #[test]
+    fn from_common_name_only_adds_hostnames() {
+        assert_eq!(
+            SubjectAltName::from_common_name("Device-1.example.com"),
+            Some(SubjectAltName::Dns("device-1.example.com".to_string()))
+        );
+        assert_eq!(SubjectAltName::from_common_name("device-1"), None);
+        assert_eq!(SubjectAltName::from_common_name("10.0.0.1"), None);
+        assert_eq!(SubjectAltName::from_common_name("Very Inc."), None);
+    }
+}
@@ -0,0 +1,192 @@
+/*
+Creation of a key pair and a certificate signing request for it, to be signed by an external CA.
+*/
+use crate::certificate;
+use crate::crypto;
+use crate::Error;
+use clap::ArgMatches;
+use colored::*;
+use std::path::PathBuf;
+
+pub struct Config {
+    crypto_config: crypto::RequestConfig,
+    encrypt_private_key: bool,
+    no_input: bool,
+    output_directory: PathBuf,
+    private_key_path: Option<PathBuf>,
+    public_key_path: Option<PathBuf>,
+    request_path: PathBuf,
+}
+
+pub fn run(clap_matches: &ArgMatches) -> Result<(), Error> {
+    match create_config(clap_matches) {
+        Ok(config) => {
+            let credentials = crypto::create_certificate_request(&config.crypto_config)?;
+
+            certificate::write_outputs(
+                vec![
+                    (config.private_key_path.as_ref(), credentials.private_key),
+                    (config.public_key_path.as_ref(), credentials.public_key),
+                    (Some(&config.request_path), Some(credentials.request)),
+                ],
+                config.no_input,
+            )
+        }
+        Err(Error::CreateAborted) => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
+fn create_config(clap_matches: &ArgMatches) -> Result<Config, Error> {
+    let subject = certificate::resolve_subject(clap_matches)?;
+    let subject_alt_names =
+        certificate::resolve_subject_alt_names(clap_matches, subject.common_name().unwrap())?;
+    let file_stem = subject.file_stem()?;
+
+    let output_directory = PathBuf::from(
+        clap_matches
+            .value_of("output-directory")
+            .unwrap()
+            .to_string(),
+    )
+    .canonicalize()?;
+
+    let output_format = certificate::resolve_output_format(clap_matches.value_of("output-format"));
+    let extension = output_format.to_string().to_lowercase();
+
+    let request_path = output_directory.join(format!("{}-csr.{}", file_stem, extension));
+
+    let export_public_key = clap_matches.is_present("export-public-key");
+
+    let public_key_path = match export_public_key {
+        true => Some(output_directory.join(format!("{}-public-key.{}", file_stem, extension))),
+        false => None,
+    };
+
+    let existing_private_key_path = match clap_matches.value_of("private-key-path") {
+        Some(existing_private_key_path_str) => {
+            Some(PathBuf::from(existing_private_key_path_str).canonicalize()?)
+        }
+        None => None,
+    };
+
+    let private_key_path = match existing_private_key_path {
+        Some(_) => None,
+        None => Some(output_directory.join(format!("{}-private-key.{}", file_stem, extension))),
+    };
+
+    let mut config = Config {
+        crypto_config: crypto::RequestConfig {
+            custom_extensions: certificate::resolve_custom_extensions(clap_matches, &[])?,
+            existing_private_key_passphrase: None,
+            export_public_key,
+            key_encoding: certificate::resolve_key_encoding(clap_matches.value_of("key-encoding")),
+            key_type: certificate::resolve_key_type(clap_matches)?,
+            output_format,
+            private_key_passphrase: None,
+            private_key_path: existing_private_key_path,
+            profile: clap_matches
+                .value_of("profile")
+                .map(|raw_profile| certificate::resolve_profile(Some(raw_profile))),
+            signature_hash: certificate::resolve_signature_hash(
+                clap_matches.value_of("signature-hash"),
+            ),
+            subject,
+            subject_alt_names,
+        },
+        encrypt_private_key: clap_matches.is_present("encrypt-private-key"),
+        no_input: clap_matches.is_present("no-input"),
+        output_directory,
+        private_key_path,
+        public_key_path,
+        request_path,
+    };
+
+    certificate::check_key_encoding(
+        &config.crypto_config.key_encoding,
+        match config.crypto_config.private_key_path {
+            Some(_) => None,
+            None => Some(&config.crypto_config.key_type),
+        },
+        config.encrypt_private_key,
+    )?;
+
+    if !config.no_input {
+        verify_config(&config)?;
+    }
+
+    if let Some(private_key_path) = &config.crypto_config.private_key_path {
+        config.crypto_config.existing_private_key_passphrase =
+            certificate::resolve_existing_private_key_passphrase(
+                clap_matches,
+                private_key_path,
+                config.no_input,
+            )?;
+    }
+
+    config.crypto_config.private_key_passphrase =
+        certificate::resolve_private_key_passphrase(clap_matches, config.no_input)?;
+
+    Ok(config)
+}
+
+fn verify_config(config: &Config) -> Result<(), Error> {
+    // Key generation options do not apply when an existing private key is used.
+    let generated_key_type = match config.crypto_config.private_key_path {
+        Some(_) => None,
+        None => Some(&config.crypto_config.key_type),
+    };
+
+    print!(
+        "\
+A certificate signing request will be created using the following configuration.
+
+private key path: {}
+encrypt private key: {}
+key encoding: {}
+key type: {}
+signature hash: {}
+profile: {}
+subject: {}
+subject alternative names: {}
+custom extensions: {}
+output directory: {}
+",
+        config
+            .crypto_config
+            .private_key_path
+            .as_ref()
+            .unwrap_or(&PathBuf::from("not applicable"))
+            .display()
+            .to_string()
+            .cyan(),
+        config.encrypt_private_key.to_string().cyan(),
+        config.crypto_config.key_encoding.to_string().cyan(),
+        generated_key_type
+            .map(|key_type| key_type.to_string().to_lowercase())
+            .unwrap_or_else(|| "not applicable".to_string())
+            .cyan(),
+        config
+            .crypto_config
+            .signature_hash
+            .map(|signature_hash| signature_hash.to_string())
+            .unwrap_or_else(|| "matching the private key".to_string())
+            .cyan(),
+        config
+            .crypto_config
+            .profile
+            .map(|profile| profile.to_string())
+            .unwrap_or_else(|| "none".to_string())
+            .cyan(),
+        config.crypto_config.subject.to_string().cyan(),
+        certificate::format_list(&config.crypto_config.subject_alt_names).cyan(),
+        certificate::format_list(&config.crypto_config.custom_extensions).cyan(),
+        config.output_directory.display().to_string().cyan(),
+    );
+
+    certificate::confirm(&[
+        config.private_key_path.as_ref(),
+        config.public_key_path.as_ref(),
+        Some(&config.request_path),
+    ])
+}
@@ -5,17 +5,74 @@ executes the relevant command.
 pub mod certificate;
 pub mod command;
 pub mod crypto;
+pub mod csr;
+pub mod passphrase;
+pub mod renewal;
 
+use chrono::{DateTime, SecondsFormat, Utc};
 use clap::{clap_app, crate_version, App};
 use colored::*;
 use command::create_ca_certificate;
 use command::create_certificate;
+use command::create_csr;
+use command::cross_sign;
+use command::renew;
+use command::sign_csr;
 use std::fmt;
+use std::path::PathBuf;
 
 #[derive(Debug)]
 pub enum Error {
     CreateAborted,
     CryptoFailed,
+    InvalidKeySize(u32),
+    InvalidPublicExponent(u32),
+    InvalidSubject(String),
+    InvalidSubjectAltName(String),
+    InvalidDuration(String),
+    InvalidTimestamp(String),
+    InvalidValidity(String),
+    SignerCannotSignCertificates,
+    SignerExpired(DateTime<Utc>),
+    SignerKeyMismatch,
+    SignerNotCa,
+    SignerNotYetValid(DateTime<Utc>),
+    ValidityExceedsSigner {
+        not_after: String,
+        signer_not_after: String,
+    },
+    InvalidExtension(String),
+    InvalidPolicy(String),
+    InvalidUrl(String),
+    InvalidIssuerUrlsFile {
+        path: PathBuf,
+        line: usize,
+        reason: String,
+    },
+    InvalidNameConstraint(String),
+    NameConstraintViolation {
+        name: String,
+        reason: String,
+    },
+    PathLengthExceeded {
+        path_length: Option<u32>,
+        signer_path_length: u32,
+    },
+    SignerPathLengthExhausted,
+    RequestSignatureInvalid(PathBuf),
+    CrossSignedCertificateNotCa(PathBuf),
+    EmptyPassphrase,
+    EncryptedTraditionalKeyEncoding,
+    PassphraseMismatch,
+    PrivateKeyDecryptionFailed(PathBuf),
+    PrivateKeyExists(PathBuf),
+    TraditionalKeyEncodingUnavailable,
+    UnsupportedKeyType,
+    UnusableExistingKeyType(Box<Error>),
+    PassphraseRequired {
+        file_arg: &'static str,
+        env_var: &'static str,
+    },
     StdError(std::io::Error),
     Crypto(openssl::error::ErrorStack),
 }
@@ -37,6 +94,143 @@ impl fmt::Display for Error {
         match self {
             Error::Crypto(err) => err.fmt(f),
             Error::StdError(err) => err.fmt(f),
+            Error::InvalidKeySize(key_size) => write!(
+                f,
+                "invalid key size {}, RSA keys must be between {} and {} bits",
+                key_size,
+                crypto::MIN_RSA_KEY_SIZE,
+                crypto::MAX_RSA_KEY_SIZE
+            ),
+            Error::InvalidPublicExponent(public_exponent) => write!(
+                f,
+                "invalid public exponent {}, it must be an odd number greater than 1",
+                public_exponent
+            ),
+            Error::InvalidSubject(reason) => write!(f, "invalid subject: {}", reason),
+            Error::InvalidSubjectAltName(reason) => {
+                write!(f, "invalid subject alternative name: {}", reason)
+            }
+            Error::InvalidDuration(duration) => write!(
+                f,
+                "invalid duration \"{}\", use a number followed by s, m, h, d or w, e.g. 12h or 1h30m",
+                duration
+            ),
+            Error::InvalidTimestamp(timestamp) => write!(
+                f,
+                "invalid timestamp \"{}\", use RFC 3339, e.g. 2026-11-01T00:00:00Z",
+                timestamp
+            ),
+            Error::InvalidValidity(reason) => write!(f, "invalid validity period: {}", reason),
+            Error::SignerCannotSignCertificates => write!(
+                f,
+                "the signer certificate's key usage does not include keyCertSign, so it cannot sign certificates"
+            ),
+            Error::SignerExpired(not_after) => write!(
+                f,
+                "the signer certificate expired at {}",
+                not_after.to_rfc3339_opts(SecondsFormat::Secs, true)
+            ),
+            Error::SignerKeyMismatch => write!(
+                f,
+                "the signer private key does not belong to the signer certificate"
+            ),
+            Error::SignerNotCa => write!(
+                f,
+                "the signer certificate is not a CA certificate, its basic constraints do not set cA"
+            ),
+            Error::SignerNotYetValid(not_before) => write!(
+                f,
+                "the signer certificate is not valid before {}",
+                not_before.to_rfc3339_opts(SecondsFormat::Secs, true)
+            ),
+            Error::ValidityExceedsSigner {
+                not_after,
+                signer_not_after,
+            } => write!(
+                f,
+                "the certificate would be valid until {}, but the signer certificate expires at {}, shorten the validity period or use --clamp-to-signer",
+                not_after, signer_not_after
+            ),
+            Error::InvalidExtension(reason) => write!(f, "invalid extension: {}", reason),
+            Error::InvalidPolicy(reason) => write!(f, "invalid certificate policy: {}", reason),
+            Error::InvalidUrl(url) => write!(
+                f,
+                "invalid URL \"{}\", it must be an absolute http, https or ldap URL without commas",
+                url
+            ),
+            Error::InvalidIssuerUrlsFile { path, line, reason } => write!(
+                f,
+                "invalid issuer URL file {}, line {}: {}",
+                path.display(),
+                line,
+                reason
+            ),
+            Error::InvalidNameConstraint(reason) => write!(f, "invalid name constraint: {}", reason),
+            Error::NameConstraintViolation { name, reason } => write!(
+                f,
+                "the signer certificate's name constraints do not allow {} ({})",
+                name, reason
+            ),
+            Error::PathLengthExceeded {
+                path_length,
+                signer_path_length,
+            } => write!(
+                f,
+                "the signer certificate's path length of {} requires a --path-length below {}, but {} was requested",
+                signer_path_length,
+                signer_path_length,
+                path_length
+                    .map(|path_length| path_length.to_string())
+                    .unwrap_or_else(|| "no limit".to_string()),
+            ),
+            Error::SignerPathLengthExhausted => write!(
+                f,
+                "the signer certificate has a path length of 0 and cannot sign CA certificates"
+            ),
+            Error::RequestSignatureInvalid(request_path) => write!(
+                f,
+                "the certificate signing request {} is not signed by the key it contains",
+                request_path.display()
+            ),
+            Error::CrossSignedCertificateNotCa(certificate_path) => write!(
+                f,
+                "{} is not a CA certificate, only CA certificates can be cross-signed",
+                certificate_path.display()
+            ),
+            Error::EmptyPassphrase => write!(f, "the passphrase must not be empty"),
+            Error::EncryptedTraditionalKeyEncoding => write!(
+                f,
+                "encrypted private keys can only be written with the pkcs8 key encoding"
+            ),
+            Error::PassphraseMismatch => write!(f, "the passphrases do not match"),
+            Error::PrivateKeyDecryptionFailed(private_key_path) => write!(
+                f,
+                "could not decrypt the private key {}, check the passphrase",
+                private_key_path.display()
+            ),
+            Error::PrivateKeyExists(private_key_path) => write!(
+                f,
+                "{} already exists, move it away or choose another --output-directory",
+                private_key_path.display()
+            ),
+            Error::UnusableExistingKeyType(reason) => write!(
+                f,
+                "a new key like the existing one cannot be created ({}), choose one with --key-type and --key-size",
+                reason
+            ),
+            Error::TraditionalKeyEncodingUnavailable => write!(
+                f,
+                "the traditional key encoding is only available for ec and rsa keys"
+            ),
+            Error::UnsupportedKeyType => write!(
+                f,
+                "no key like the one of the renewed certificate can be created, choose one with --key-type"
+            ),
+            Error::PassphraseRequired { file_arg, env_var } => write!(
+                f,
+                "a passphrase is required, provide it via --{} or the {} environment variable",
+                file_arg, env_var
+            ),
             _ => Ok(()),
         }
     }
@@ -57,6 +251,10 @@ fn run_cli() -> Result<(), Error> {
         Some(("create-certificate", subcommand_matches)) => {
             create_certificate::run(subcommand_matches)
         }
+        Some(("create-csr", subcommand_matches)) => create_csr::run(subcommand_matches),
+        Some(("sign-csr", subcommand_matches)) => sign_csr::run(subcommand_matches),
+        Some(("cross-sign", subcommand_matches)) => cross_sign::run(subcommand_matches),
+        Some(("renew", subcommand_matches)) => renew::run(subcommand_matches),
         _ => print_help(),
     };
 
@@ -86,36 +284,265 @@ fn get_app() -> App<'static> {
         (author: "Very")
         (about: "Creation of key pairs and certificates for use with TLS.")
         (@subcommand "create-ca-certificate" =>
-            (about: "Create a CA certificate and a key pair.")
+            (about: "Create a CA certificate and a key pair, or a CA certificate for an existing private key.")
+            (@arg ("private-key-path"): --("private-key-path") +takes_value "A path to an existing pem or der encoded private key to create the certificate for instead of creating a new key pair.")
+            (@arg ("private-key-passphrase-file"): --("private-key-passphrase-file") requires("private-key-path") +takes_value "A path to a file containing the passphrase of an encrypted existing private key. Without it the passphrase is taken from the TCH_PRIVATE_KEY_PASSPHRASE environment variable or a prompt.")
+            (@arg ("key-encoding"): --("key-encoding") +takes_value default_value[pkcs8] possible_value[pkcs8 traditional] "Sets the structure of the created private key for both output formats: pkcs8, or the key type specific SEC1 or PKCS#1 structure (ec and rsa keys only).")
+            (@arg ("export-public-key"): --("export-public-key") "Also writes the public key as a SubjectPublicKeyInfo structure, e.g. for pinning.")
+            (@arg ("encrypt-private-key"): --("encrypt-private-key") conflicts_with("private-key-path") "Encrypts the created private key as PKCS#8 with AES-256 and a passphrase taken from --passphrase-file, the TCH_PASSPHRASE environment variable, or a prompt.")
+            (@arg ("passphrase-file"): --("passphrase-file") requires("encrypt-private-key") +takes_value "A path to a file containing the passphrase used to encrypt the created private key.")
             (@arg ("output-format"): --("output-format") +takes_value default_value[pem] possible_value[der pem] "Sets the output file format.")
-            (@arg ("key-type"): --("key-type") +takes_value default_value[ec] possible_value[ec rsa] "Sets the type of the created keys.")
-            (@arg ("days-valid"): --("days-valid") +takes_value +required "How may days from today the created certificate will be valid for.")
+            (@arg ("key-type"): --("key-type") +takes_value default_value[ec] possible_value["ec" "ed25519" "ed448" "rsa" "rsa-pss"] "Sets the type of the created keys.")
+            (@arg curve: --curve +takes_value default_value[p256] possible_value[p256 p384 p521 secp256k1] "Sets the elliptic curve of the created keys when --key-type is ec.")
+            (@arg ("key-size"): --("key-size") +takes_value default_value["2048"] "Sets the size in bits, from 2048 to 16384, of the created keys when --key-type is rsa or rsa-pss.")
+            (@arg ("public-exponent"): --("public-exponent") +takes_value default_value["65537"] "Sets the public exponent of the created keys when --key-type is rsa or rsa-pss.")
+            (@arg ("signature-hash"): --("signature-hash") +takes_value possible_value[sha256 sha384 sha512] "Sets the hash algorithm of the certificate signature. Defaults to one matching the signing key, e.g. sha256 for P-256 and sha384 for P-384. Ignored for Ed25519 and Ed448 signing keys.")
+            (@arg ("signature-scheme"): --("signature-scheme") +takes_value default_value[pkcs1v15] possible_value[pkcs1v15 pss] "Sets the padding of the certificate signature when the signing key is an RSA key. RSA-PSS signing keys always use pss.")
+            (@arg ("not-before"): --("not-before") +takes_value "Sets the start of the validity period to the given RFC 3339 timestamp, e.g. 2026-11-01T00:00:00Z. Defaults to now minus --backdate.")
+            (@arg backdate: --backdate +takes_value default_value["5m"] conflicts_with("not-before") "How long before now the validity period starts, so that devices whose clock is behind accept the certificate. Durations are a number followed by s, m, h, d or w, e.g. 90s or 1h30m.")
+            (@group validity =>
+                (@attributes +required)
+                (@arg ("days-valid"): --("days-valid") +takes_value "How may days from today, or from --not-before, the created certificate will be valid for.")
+                (@arg ("valid-for"): --("valid-for") +takes_value "How long from now, or from --not-before, the created certificate will be valid for, e.g. 12h or 90d.")
+                (@arg ("not-after"): --("not-after") +takes_value "Sets the end of the validity period to the given RFC 3339 timestamp.")
+                (@arg ("no-well-defined-expiry"): --("no-well-defined-expiry") "Sets the end of the validity period to 99991231235959Z, which RFC 5280 defines as no well-defined expiration date, e.g. for device identity certificates.")
+            )
             (@arg ("output-directory"): --("output-directory") +takes_value default_value["."] "Sets the output directory.")
             (@arg ("no-input"): --("no-input") "Runs the CLI in no-input mode.")
             (@group namee =>
                 (@attributes +required)
                 (@arg ("common-name"): --("common-name") +takes_value "Sets the created certificate's common name to the provided value.")
                 (@arg ("random-common-name"): --("random-common-name") "Sets the created certificate's common name to a generated version 4 UUID.")
+                (@arg subject: --subject +takes_value "Sets the created certificate's subject to the provided RFC 4514 distinguished name, e.g. \"CN=device,O=Very,C=US\". It must include a common name.")
             )
+            (@arg organization: --organization conflicts_with("subject") +takes_value "Sets the created certificate's organization (O).")
+            (@arg ("organizational-unit"): --("organizational-unit") conflicts_with("subject") +takes_value "Sets the created certificate's organizational unit (OU).")
+            (@arg country: --country conflicts_with("subject") +takes_value "Sets the created certificate's two-letter country code (C).")
+            (@arg state: --state conflicts_with("subject") +takes_value "Sets the created certificate's state or province (ST).")
+            (@arg locality: --locality conflicts_with("subject") +takes_value "Sets the created certificate's locality (L).")
+            (@arg ("subject-serial-number"): --("subject-serial-number") conflicts_with("subject") +takes_value "Sets the created certificate's subject serialNumber attribute, e.g. a device serial number.")
+            (@arg ("email-address"): --("email-address") conflicts_with("subject") +takes_value "Sets the created certificate's subject emailAddress attribute.")
+            (@arg ("name-encoding"): --("name-encoding") +takes_value default_value[utf8] possible_value[utf8 printable] "Sets the string type of the subject attributes. Country codes and serial numbers are always printable.")
+            (@arg ("path-length"): --("path-length") +takes_value "The maximum number of intermediate CA certificates that may follow the created CA certificate in a path. Defaults to no limit for self-signed CA certificates and to 0 for CA certificates signed by another CA.")
+            (@arg ("permitted-dns"): --("permitted-dns") +takes_value +multiple_occurrences "Restricts the DNS names of certificates below the CA to the given domain and its subdomains. May be repeated.")
+            (@arg ("permitted-ip"): --("permitted-ip") +takes_value +multiple_occurrences "Restricts the IP addresses of certificates below the CA to the given CIDR range, e.g. 10.0.0.0/8. May be repeated.")
+            (@arg ("permitted-email"): --("permitted-email") +takes_value +multiple_occurrences "Restricts the email addresses of certificates below the CA to the given mailbox, host or, with a leading dot, domain. May be repeated.")
+            (@arg ("excluded-dns"): --("excluded-dns") +takes_value +multiple_occurrences "Forbids the given domain and its subdomains as DNS names of certificates below the CA. May be repeated.")
+            (@arg ("excluded-ip"): --("excluded-ip") +takes_value +multiple_occurrences "Forbids the given CIDR range as IP addresses of certificates below the CA. May be repeated.")
+            (@arg ("excluded-email"): --("excluded-email") +takes_value +multiple_occurrences "Forbids the given mailbox, host or, with a leading dot, domain as email addresses of certificates below the CA. May be repeated.")
+            (@arg ("crl-distribution-point"): --("crl-distribution-point") +takes_value +multiple_occurrences "Adds a URL where the signer publishes its certificate revocation list. Replaces the ones from the signer's issuer URL file. May be repeated.")
+            (@arg ("ocsp-url"): --("ocsp-url") +takes_value +multiple_occurrences "Adds the URL of an OCSP responder for the signer to the authority information access. Replaces the ones from the signer's issuer URL file. May be repeated.")
+            (@arg ("ca-issuers-url"): --("ca-issuers-url") +takes_value +multiple_occurrences "Adds a URL where the signer certificate can be downloaded to the authority information access. Replaces the ones from the signer's issuer URL file. May be repeated.")
+            (@arg ("issued-crl-distribution-point"): --("issued-crl-distribution-point") +takes_value +multiple_occurrences "Adds a URL where the created CA publishes its certificate revocation list to every certificate it issues. The URLs are kept next to the created certificate. May be repeated.")
+            (@arg ("issued-ocsp-url"): --("issued-ocsp-url") +takes_value +multiple_occurrences "Adds the URL of an OCSP responder for the created CA to every certificate it issues. May be repeated.")
+            (@arg ("issued-ca-issuers-url"): --("issued-ca-issuers-url") +takes_value +multiple_occurrences "Adds a URL where the created CA certificate can be downloaded to every certificate it issues. May be repeated.")
+            (@arg policy: --policy +takes_value +multiple_occurrences "Adds a certificate policy, given as OID or as OID:CPS-URI to include a certification practice statement. May be repeated.")
+            (@arg extension: --extension +takes_value +multiple_occurrences "Adds a custom extension, given as OID:critical:DER-hex, OID:non-critical:DER-hex or OID:DER-hex. May be repeated.")
             (@arg ("self-signed"): --("self-signed") conflicts_with_all(&["signer-certificate-path", "signer-private-key-path"]) "Sign the created certificate with the created private key pair as opposed to with an existing signer provided via --signer-certificate-path and --signer-private-key-path.")
             (@arg ("signer-certificate-path"): --("signer-certificate-path") required_unless_present("self-signed") +takes_value "A path to an existing pem or der encoded signer certificate to use.")
             (@arg ("signer-private-key-path"): --("signer-private-key-path") required_unless_present("self-signed") +takes_value "A path to an existing pem or der encoded signer private key to use.")
+            (@arg ("signer-passphrase-file"): --("signer-passphrase-file") requires("signer-private-key-path") +takes_value "A path to a file containing the passphrase of an encrypted signer private key. Without it the passphrase is taken from the TCH_SIGNER_PASSPHRASE environment variable or a prompt.")
+            (@arg ("clamp-to-signer"): --("clamp-to-signer") requires("signer-certificate-path") "Shortens the validity period to end with the signer certificate's when it would otherwise outlive it, instead of refusing to create the certificate.")
         )
         (@subcommand "create-certificate" =>
-            (about: "Create a certificate and a key pair.")
+            (about: "Create a certificate and a key pair, or a certificate for an existing private or public key.")
+            (@arg ("private-key-path"): --("private-key-path") +takes_value "A path to an existing pem or der encoded private key to create the certificate for instead of creating a new key pair.")
+            (@arg ("private-key-passphrase-file"): --("private-key-passphrase-file") requires("private-key-path") +takes_value "A path to a file containing the passphrase of an encrypted existing private key. Without it the passphrase is taken from the TCH_PRIVATE_KEY_PASSPHRASE environment variable or a prompt.")
+            (@arg ("public-key-path"): --("public-key-path") conflicts_with_all(&["private-key-path", "self-signed", "encrypt-private-key"]) +takes_value "A path to an existing pem or der encoded public key (SubjectPublicKeyInfo) to create the certificate for, e.g. of a device that cannot create a certificate signing request. No private key is written.")
+            (@arg ("key-encoding"): --("key-encoding") +takes_value default_value[pkcs8] possible_value[pkcs8 traditional] "Sets the structure of the created private key for both output formats: pkcs8, or the key type specific SEC1 or PKCS#1 structure (ec and rsa keys only).")
+            (@arg ("export-public-key"): --("export-public-key") "Also writes the public key as a SubjectPublicKeyInfo structure, e.g. for pinning.")
+            (@arg ("encrypt-private-key"): --("encrypt-private-key") conflicts_with("private-key-path") "Encrypts the created private key as PKCS#8 with AES-256 and a passphrase taken from --passphrase-file, the TCH_PASSPHRASE environment variable, or a prompt.")
+            (@arg ("passphrase-file"): --("passphrase-file") requires("encrypt-private-key") +takes_value "A path to a file containing the passphrase used to encrypt the created private key.")
             (@arg ("output-format"): --("output-format") +takes_value default_value[pem] possible_value[der pem] "Sets the output file format.")
-            (@arg ("key-type"): --("key-type") +takes_value default_value[ec] possible_value[ec rsa] "Sets the type of the created keys.")
-            (@arg ("days-valid"): --("days-valid") +takes_value +required "How may days from today the created certificate will be valid for.")
+            (@arg ("key-type"): --("key-type") +takes_value default_value[ec] possible_value["ec" "ed25519" "ed448" "rsa" "rsa-pss"] "Sets the type of the created keys.")
+            (@arg curve: --curve +takes_value default_value[p256] possible_value[p256 p384 p521 secp256k1] "Sets the elliptic curve of the created keys when --key-type is ec.")
+            (@arg ("key-size"): --("key-size") +takes_value default_value["2048"] "Sets the size in bits, from 2048 to 16384, of the created keys when --key-type is rsa or rsa-pss.")
+            (@arg ("public-exponent"): --("public-exponent") +takes_value default_value["65537"] "Sets the public exponent of the created keys when --key-type is rsa or rsa-pss.")
+            (@arg ("signature-hash"): --("signature-hash") +takes_value possible_value[sha256 sha384 sha512] "Sets the hash algorithm of the certificate signature. Defaults to one matching the signing key, e.g. sha256 for P-256 and sha384 for P-384. Ignored for Ed25519 and Ed448 signing keys.")
+            (@arg ("signature-scheme"): --("signature-scheme") +takes_value default_value[pkcs1v15] possible_value[pkcs1v15 pss] "Sets the padding of the certificate signature when the signing key is an RSA key. RSA-PSS signing keys always use pss.")
+            (@arg ("not-before"): --("not-before") +takes_value "Sets the start of the validity period to the given RFC 3339 timestamp, e.g. 2026-11-01T00:00:00Z. Defaults to now minus --backdate.")
+            (@arg backdate: --backdate +takes_value default_value["5m"] conflicts_with("not-before") "How long before now the validity period starts, so that devices whose clock is behind accept the certificate. Durations are a number followed by s, m, h, d or w, e.g. 90s or 1h30m.")
+            (@group validity =>
+                (@attributes +required)
+                (@arg ("days-valid"): --("days-valid") +takes_value "How may days from today, or from --not-before, the created certificate will be valid for.")
+                (@arg ("valid-for"): --("valid-for") +takes_value "How long from now, or from --not-before, the created certificate will be valid for, e.g. 12h or 90d.")
+                (@arg ("not-after"): --("not-after") +takes_value "Sets the end of the validity period to the given RFC 3339 timestamp.")
+                (@arg ("no-well-defined-expiry"): --("no-well-defined-expiry") "Sets the end of the validity period to 99991231235959Z, which RFC 5280 defines as no well-defined expiration date, e.g. for device identity certificates.")
+            )
             (@arg ("output-directory"): --("output-directory") +takes_value default_value["."] "Sets the output directory.")
             (@arg ("no-input"): --("no-input") "Runs the CLI in no-input mode.")
             (@group name =>
                 (@attributes +required)
                 (@arg ("common-name"): --("common-name") +takes_value "Sets the created certificate's common name to the provided value.")
                 (@arg ("random-common-name"): --("random-common-name") "Sets the created certificate's common name to a generated version 4 UUID.")
+                (@arg subject: --subject +takes_value "Sets the created certificate's subject to the provided RFC 4514 distinguished name, e.g. \"CN=device,O=Very,C=US\". It must include a common name.")
             )
+            (@arg organization: --organization conflicts_with("subject") +takes_value "Sets the created certificate's organization (O).")
+            (@arg ("organizational-unit"): --("organizational-unit") conflicts_with("subject") +takes_value "Sets the created certificate's organizational unit (OU).")
+            (@arg country: --country conflicts_with("subject") +takes_value "Sets the created certificate's two-letter country code (C).")
+            (@arg state: --state conflicts_with("subject") +takes_value "Sets the created certificate's state or province (ST).")
+            (@arg locality: --locality conflicts_with("subject") +takes_value "Sets the created certificate's locality (L).")
+            (@arg ("subject-serial-number"): --("subject-serial-number") conflicts_with("subject") +takes_value "Sets the created certificate's subject serialNumber attribute, e.g. a device serial number.")
+            (@arg ("email-address"): --("email-address") conflicts_with("subject") +takes_value "Sets the created certificate's subject emailAddress attribute.")
+            (@arg ("name-encoding"): --("name-encoding") +takes_value default_value[utf8] possible_value[utf8 printable] "Sets the string type of the subject attributes. Country codes and serial numbers are always printable.")
+            (@arg profile: --profile +takes_value possible_value["client" "code-signing" "email" "mtls" "ocsp-signing" "server" "time-stamping"] default_value("mtls") "The intended use of the certificate, which determines its key usage and extended key usage.")
+            (@arg dns: --dns +takes_value +multiple_occurrences "Adds a DNS name, optionally a wildcard or internationalized name, to the subject alternative names. May be repeated. A common name that looks like a hostname is added automatically.")
+            (@arg ip: --ip +takes_value +multiple_occurrences "Adds an IPv4 or IPv6 address to the subject alternative names. May be repeated.")
+            (@arg email: --email +takes_value +multiple_occurrences "Adds an email address to the subject alternative names. May be repeated.")
+            (@arg uri: --uri +takes_value +multiple_occurrences "Adds a URI to the subject alternative names. May be repeated.")
+            (@arg ("crl-distribution-point"): --("crl-distribution-point") +takes_value +multiple_occurrences "Adds a URL where the signer publishes its certificate revocation list. Replaces the ones from the signer's issuer URL file. May be repeated.")
+            (@arg ("ocsp-url"): --("ocsp-url") +takes_value +multiple_occurrences "Adds the URL of an OCSP responder for the signer to the authority information access. Replaces the ones from the signer's issuer URL file. May be repeated.")
+            (@arg ("ca-issuers-url"): --("ca-issuers-url") +takes_value +multiple_occurrences "Adds a URL where the signer certificate can be downloaded to the authority information access. Replaces the ones from the signer's issuer URL file. May be repeated.")
+            (@arg policy: --policy +takes_value +multiple_occurrences "Adds a certificate policy, given as OID or as OID:CPS-URI to include a certification practice statement. May be repeated.")
+            (@arg extension: --extension +takes_value +multiple_occurrences "Adds a custom extension, given as OID:critical:DER-hex, OID:non-critical:DER-hex or OID:DER-hex. May be repeated.")
             (@arg ("self-signed"): --("self-signed") conflicts_with_all(&["signer-certificate-path", "signer-private-key-path"]) "Sign the created certificate with the created private key pair as opposed to with an existing signer provided via --signer-certificate-path and --signer-private-key-path.")
             (@arg ("signer-certificate-path"): --("signer-certificate-path") required_unless_present("self-signed") +takes_value "A path to an existing pem or der encoded signer certificate to use.")
             (@arg ("signer-private-key-path"): --("signer-private-key-path") required_unless_present("self-signed") +takes_value "A path to an existing pem or der encoded signer private key to use.")
+            (@arg ("signer-passphrase-file"): --("signer-passphrase-file") requires("signer-private-key-path") +takes_value "A path to a file containing the passphrase of an encrypted signer private key. Without it the passphrase is taken from the TCH_SIGNER_PASSPHRASE environment variable or a prompt.")
+            (@arg ("clamp-to-signer"): --("clamp-to-signer") requires("signer-certificate-path") "Shortens the validity period to end with the signer certificate's when it would otherwise outlive it, instead of refusing to create the certificate.")
+        )
+        (@subcommand "create-csr" =>
+            (about: "Create a key pair and a certificate signing request for it, or a certificate signing request for an existing private key.")
+            (@arg ("private-key-path"): --("private-key-path") +takes_value "A path to an existing pem or der encoded private key to create the request for instead of creating a new key pair.")
+            (@arg ("private-key-passphrase-file"): --("private-key-passphrase-file") requires("private-key-path") +takes_value "A path to a file containing the passphrase of an encrypted existing private key. Without it the passphrase is taken from the TCH_PRIVATE_KEY_PASSPHRASE environment variable or a prompt.")
+            (@arg ("key-encoding"): --("key-encoding") +takes_value default_value[pkcs8] possible_value[pkcs8 traditional] "Sets the structure of the created private key for both output formats: pkcs8, or the key type specific SEC1 or PKCS#1 structure (ec and rsa keys only).")
+            (@arg ("export-public-key"): --("export-public-key") "Also writes the public key as a SubjectPublicKeyInfo structure, e.g. for pinning.")
+            (@arg ("encrypt-private-key"): --("encrypt-private-key") conflicts_with("private-key-path") "Encrypts the created private key as PKCS#8 with AES-256 and a passphrase taken from --passphrase-file, the TCH_PASSPHRASE environment variable, or a prompt.")
+            (@arg ("passphrase-file"): --("passphrase-file") requires("encrypt-private-key") +takes_value "A path to a file containing the passphrase used to encrypt the created private key.")
+            (@arg ("output-format"): --("output-format") +takes_value default_value[pem] possible_value[der pem] "Sets the output file format.")
+            (@arg ("key-type"): --("key-type") +takes_value default_value[ec] possible_value["ec" "ed25519" "ed448" "rsa" "rsa-pss"] "Sets the type of the created keys.")
+            (@arg curve: --curve +takes_value default_value[p256] possible_value[p256 p384 p521 secp256k1] "Sets the elliptic curve of the created keys when --key-type is ec.")
+            (@arg ("key-size"): --("key-size") +takes_value default_value["2048"] "Sets the size in bits, from 2048 to 16384, of the created keys when --key-type is rsa or rsa-pss.")
+            (@arg ("public-exponent"): --("public-exponent") +takes_value default_value["65537"] "Sets the public exponent of the created keys when --key-type is rsa or rsa-pss.")
+            (@arg ("signature-hash"): --("signature-hash") +takes_value possible_value[sha256 sha384 sha512] "Sets the hash algorithm of the request signature. Defaults to one matching the private key, e.g. sha256 for P-256 and sha384 for P-384. Ignored for Ed25519 and Ed448 keys.")
+            (@arg ("output-directory"): --("output-directory") +takes_value default_value["."] "Sets the output directory.")
+            (@arg ("no-input"): --("no-input") "Runs the CLI in no-input mode.")
+            (@group name =>
+                (@attributes +required)
+                (@arg ("common-name"): --("common-name") +takes_value "Sets the requested common name to the provided value.")
+                (@arg ("random-common-name"): --("random-common-name") "Sets the requested common name to a generated version 4 UUID.")
+                (@arg subject: --subject +takes_value "Sets the requested subject to the provided RFC 4514 distinguished name, e.g. \"CN=device,O=Very,C=US\". It must include a common name.")
+            )
+            (@arg organization: --organization conflicts_with("subject") +takes_value "Sets the requested organization (O).")
+            (@arg ("organizational-unit"): --("organizational-unit") conflicts_with("subject") +takes_value "Sets the requested organizational unit (OU).")
+            (@arg country: --country conflicts_with("subject") +takes_value "Sets the requested two-letter country code (C).")
+            (@arg state: --state conflicts_with("subject") +takes_value "Sets the requested state or province (ST).")
+            (@arg locality: --locality conflicts_with("subject") +takes_value "Sets the requested locality (L).")
+            (@arg ("subject-serial-number"): --("subject-serial-number") conflicts_with("subject") +takes_value "Sets the requested subject serialNumber attribute, e.g. a device serial number.")
+            (@arg ("email-address"): --("email-address") conflicts_with("subject") +takes_value "Sets the requested subject emailAddress attribute.")
+            (@arg ("name-encoding"): --("name-encoding") +takes_value default_value[utf8] possible_value[utf8 printable] "Sets the string type of the subject attributes. Country codes and serial numbers are always printable.")
+            (@arg profile: --profile +takes_value possible_value["client" "code-signing" "email" "mtls" "ocsp-signing" "server" "time-stamping"] "Requests the key usage and extended key usage of the given certificate profile. Without it the signing CA decides.")
+            (@arg dns: --dns +takes_value +multiple_occurrences "Requests a DNS name, optionally a wildcard or internationalized name, as a subject alternative name. May be repeated. A common name that looks like a hostname is added automatically.")
+            (@arg ip: --ip +takes_value +multiple_occurrences "Requests an IPv4 or IPv6 address as a subject alternative name. May be repeated.")
+            (@arg email: --email +takes_value +multiple_occurrences "Requests an email address as a subject alternative name. May be repeated.")
+            (@arg uri: --uri +takes_value +multiple_occurrences "Requests a URI as a subject alternative name. May be repeated.")
+            (@arg extension: --extension +takes_value +multiple_occurrences "Requests a custom extension, given as OID:critical:DER-hex, OID:non-critical:DER-hex or OID:DER-hex. May be repeated.")
+        )
+        (@subcommand "sign-csr" =>
+            (about: "Create a certificate for a certificate signing request created elsewhere, e.g. with create-csr.")
+            (@arg csr: --csr +required +takes_value "A path to the pem or der encoded certificate signing request. Its signature is verified before anything is copied from it.")
+            (@arg ("output-format"): --("output-format") +takes_value default_value[pem] possible_value[der pem] "Sets the output file format.")
+            (@arg ("signature-hash"): --("signature-hash") +takes_value possible_value[sha256 sha384 sha512] "Sets the hash algorithm of the certificate signature. Defaults to one matching the signing key, e.g. sha256 for P-256 and sha384 for P-384. Ignored for Ed25519 and Ed448 signing keys.")
+            (@arg ("signature-scheme"): --("signature-scheme") +takes_value default_value[pkcs1v15] possible_value[pkcs1v15 pss] "Sets the padding of the certificate signature when the signing key is an RSA key. RSA-PSS signing keys always use pss.")
+            (@arg ("not-before"): --("not-before") +takes_value "Sets the start of the validity period to the given RFC 3339 timestamp, e.g. 2026-11-01T00:00:00Z. Defaults to now minus --backdate.")
+            (@arg backdate: --backdate +takes_value default_value["5m"] conflicts_with("not-before") "How long before now the validity period starts, so that devices whose clock is behind accept the certificate. Durations are a number followed by s, m, h, d or w, e.g. 90s or 1h30m.")
+            (@group validity =>
+                (@attributes +required)
+                (@arg ("days-valid"): --("days-valid") +takes_value "How may days from today, or from --not-before, the created certificate will be valid for.")
+                (@arg ("valid-for"): --("valid-for") +takes_value "How long from now, or from --not-before, the created certificate will be valid for, e.g. 12h or 90d.")
+                (@arg ("not-after"): --("not-after") +takes_value "Sets the end of the validity period to the given RFC 3339 timestamp.")
+                (@arg ("no-well-defined-expiry"): --("no-well-defined-expiry") "Sets the end of the validity period to 99991231235959Z, which RFC 5280 defines as no well-defined expiration date, e.g. for device identity certificates.")
+            )
+            (@arg ("output-directory"): --("output-directory") +takes_value default_value["."] "Sets the output directory.")
+            (@arg ("no-input"): --("no-input") "Runs the CLI in no-input mode.")
+            (@arg subject: --subject +takes_value "Overrides the requested subject with the provided RFC 4514 distinguished name, e.g. \"CN=device,O=Very,C=US\". It must include a common name.")
+            (@arg ("name-encoding"): --("name-encoding") +takes_value default_value[utf8] possible_value[utf8 printable] "Sets the string type of the subject attributes, including the ones copied from the request. Country codes and serial numbers are always printable.")
+            (@arg profile: --profile +takes_value possible_value["client" "code-signing" "email" "mtls" "ocsp-signing" "server" "time-stamping"] default_value("mtls") "The intended use of the certificate, which determines its key usage and extended key usage regardless of the ones requested.")
+            (@arg ("copy-subject-alt-names"): --("copy-subject-alt-names") conflicts_with("copy-subject-alt-name") "Copies the requested subject alternative names into the certificate, skipping names of an unsupported type. Without it only the names given with --dns, --ip, --email and --uri, and the common name if it looks like a hostname, are included.")
+            (@arg ("copy-subject-alt-name"): --("copy-subject-alt-name") +takes_value +multiple_occurrences "Copies the requested subject alternative name given as DNS:, IP:, email: or URI: followed by the name, e.g. DNS:device-1.example.com. May be repeated.")
+            (@arg dns: --dns +takes_value +multiple_occurrences "Adds a DNS name, optionally a wildcard or internationalized name, to the subject alternative names. May be repeated.")
+            (@arg ip: --ip +takes_value +multiple_occurrences "Adds an IPv4 or IPv6 address to the subject alternative names. May be repeated.")
+            (@arg email: --email +takes_value +multiple_occurrences "Adds an email address to the subject alternative names. May be repeated.")
+            (@arg uri: --uri +takes_value +multiple_occurrences "Adds a URI to the subject alternative names. May be repeated.")
+            (@arg ("copy-extension"): --("copy-extension") +takes_value +multiple_occurrences "Copies the requested extension with the given OID into the certificate unchanged. Requested extensions are left out otherwise. May be repeated.")
+            (@arg ("crl-distribution-point"): --("crl-distribution-point") +takes_value +multiple_occurrences "Adds a URL where the signer publishes its certificate revocation list. Replaces the ones from the signer's issuer URL file. May be repeated.")
+            (@arg ("ocsp-url"): --("ocsp-url") +takes_value +multiple_occurrences "Adds the URL of an OCSP responder for the signer to the authority information access. Replaces the ones from the signer's issuer URL file. May be repeated.")
+            (@arg ("ca-issuers-url"): --("ca-issuers-url") +takes_value +multiple_occurrences "Adds a URL where the signer certificate can be downloaded to the authority information access. Replaces the ones from the signer's issuer URL file. May be repeated.")
+            (@arg policy: --policy +takes_value +multiple_occurrences "Adds a certificate policy, given as OID or as OID:CPS-URI to include a certification practice statement. May be repeated.")
+            (@arg extension: --extension +takes_value +multiple_occurrences "Adds a custom extension, given as OID:critical:DER-hex, OID:non-critical:DER-hex or OID:DER-hex, in place of a requested extension with the same OID. May be repeated.")
+            (@arg ("signer-certificate-path"): --("signer-certificate-path") +required +takes_value "A path to an existing pem or der encoded signer certificate to use.")
+            (@arg ("signer-private-key-path"): --("signer-private-key-path") +required +takes_value "A path to an existing pem or der encoded signer private key to use.")
+            (@arg ("signer-passphrase-file"): --("signer-passphrase-file") +takes_value "A path to a file containing the passphrase of an encrypted signer private key. Without it the passphrase is taken from the TCH_SIGNER_PASSPHRASE environment variable or a prompt.")
+            (@arg ("clamp-to-signer"): --("clamp-to-signer") "Shortens the validity period to end with the signer certificate's when it would otherwise outlive it, instead of refusing to create the certificate.")
+        )
+        (@subcommand "cross-sign" =>
+            (about: "Create a CA certificate for the subject and public key of an existing CA certificate, signed by another CA, e.g. to rotate roots.")
+            (@arg certificate: --certificate +required +takes_value "A path to the pem or der encoded CA certificate to cross-sign. Its subject, public key and subject key identifier are copied unchanged, and so are its name constraints unless --permitted-* or --excluded-* options are given.")
+            (@arg ("export-public-key"): --("export-public-key") "Also writes the public key as a SubjectPublicKeyInfo structure, e.g. for pinning.")
+            (@arg ("output-format"): --("output-format") +takes_value default_value[pem] possible_value[der pem] "Sets the output file format.")
+            (@arg ("signature-hash"): --("signature-hash") +takes_value possible_value[sha256 sha384 sha512] "Sets the hash algorithm of the certificate signature. Defaults to one matching the signing key, e.g. sha256 for P-256 and sha384 for P-384. Ignored for Ed25519 and Ed448 signing keys.")
+            (@arg ("signature-scheme"): --("signature-scheme") +takes_value default_value[pkcs1v15] possible_value[pkcs1v15 pss] "Sets the padding of the certificate signature when the signing key is an RSA key. RSA-PSS signing keys always use pss.")
+            (@arg ("not-before"): --("not-before") +takes_value "Sets the start of the validity period to the given RFC 3339 timestamp, e.g. 2026-11-01T00:00:00Z. Defaults to now minus --backdate.")
+            (@arg backdate: --backdate +takes_value default_value["5m"] conflicts_with("not-before") "How long before now the validity period starts, so that devices whose clock is behind accept the certificate. Durations are a number followed by s, m, h, d or w, e.g. 90s or 1h30m.")
+            (@group validity =>
+                (@attributes +required)
+                (@arg ("days-valid"): --("days-valid") +takes_value "How may days from today, or from --not-before, the created certificate will be valid for.")
+                (@arg ("valid-for"): --("valid-for") +takes_value "How long from now, or from --not-before, the created certificate will be valid for, e.g. 12h or 90d.")
+                (@arg ("not-after"): --("not-after") +takes_value "Sets the end of the validity period to the given RFC 3339 timestamp.")
+                (@arg ("no-well-defined-expiry"): --("no-well-defined-expiry") "Sets the end of the validity period to 99991231235959Z, which RFC 5280 defines as no well-defined expiration date, e.g. for device identity certificates.")
+            )
+            (@arg ("output-directory"): --("output-directory") +takes_value default_value["."] "Sets the output directory.")
+            (@arg ("no-input"): --("no-input") "Runs the CLI in no-input mode.")
+            (@arg ("path-length"): --("path-length") +takes_value "The maximum number of intermediate CA certificates that may follow the created CA certificate in a path. Defaults to the path length of the cross-signed certificate.")
+            (@arg ("permitted-dns"): --("permitted-dns") +takes_value +multiple_occurrences "Restricts the DNS names of certificates below the CA to the given domain and its subdomains. May be repeated.")
+            (@arg ("permitted-ip"): --("permitted-ip") +takes_value +multiple_occurrences "Restricts the IP addresses of certificates below the CA to the given CIDR range, e.g. 10.0.0.0/8. May be repeated.")
+            (@arg ("permitted-email"): --("permitted-email") +takes_value +multiple_occurrences "Restricts the email addresses of certificates below the CA to the given mailbox, host or, with a leading dot, domain. May be repeated.")
+            (@arg ("excluded-dns"): --("excluded-dns") +takes_value +multiple_occurrences "Forbids the given domain and its subdomains as DNS names of certificates below the CA. May be repeated.")
+            (@arg ("excluded-ip"): --("excluded-ip") +takes_value +multiple_occurrences "Forbids the given CIDR range as IP addresses of certificates below the CA. May be repeated.")
+            (@arg ("excluded-email"): --("excluded-email") +takes_value +multiple_occurrences "Forbids the given mailbox, host or, with a leading dot, domain as email addresses of certificates below the CA. May be repeated.")
+            (@arg ("crl-distribution-point"): --("crl-distribution-point") +takes_value +multiple_occurrences "Adds a URL where the signer publishes its certificate revocation list. Replaces the ones from the signer's issuer URL file. May be repeated.")
+            (@arg ("ocsp-url"): --("ocsp-url") +takes_value +multiple_occurrences "Adds the URL of an OCSP responder for the signer to the authority information access. Replaces the ones from the signer's issuer URL file. May be repeated.")
+            (@arg ("ca-issuers-url"): --("ca-issuers-url") +takes_value +multiple_occurrences "Adds a URL where the signer certificate can be downloaded to the authority information access. Replaces the ones from the signer's issuer URL file. May be repeated.")
+            (@arg policy: --policy +takes_value +multiple_occurrences "Adds a certificate policy, given as OID or as OID:CPS-URI to include a certification practice statement. May be repeated.")
+            (@arg extension: --extension +takes_value +multiple_occurrences "Adds a custom extension, given as OID:critical:DER-hex, OID:non-critical:DER-hex or OID:DER-hex. May be repeated.")
+            (@arg ("signer-certificate-path"): --("signer-certificate-path") +required +takes_value "A path to an existing pem or der encoded signer certificate to use.")
+            (@arg ("signer-private-key-path"): --("signer-private-key-path") +required +takes_value "A path to an existing pem or der encoded signer private key to use.")
+            (@arg ("signer-passphrase-file"): --("signer-passphrase-file") +takes_value "A path to a file containing the passphrase of an encrypted signer private key. Without it the passphrase is taken from the TCH_SIGNER_PASSPHRASE environment variable or a prompt.")
+            (@arg ("clamp-to-signer"): --("clamp-to-signer") "Shortens the validity period to end with the signer certificate's when it would otherwise outlive it, instead of refusing to create the certificate.")
+        )
+        (@subcommand renew =>
+            (about: "Create a replacement for an existing certificate with the same subject, subject alternative names and extensions, but a new serial number and validity period. The issuer URLs are taken from the signer, not the existing certificate.")
+            (@arg certificate: --certificate +required +takes_value "A path to the pem or der encoded certificate to renew.")
+            (@arg rekey: --rekey "Creates a new key pair for the renewed certificate. Without it the key of the existing certificate is kept, so that pins stay valid.")
+            (@arg ("crl-distribution-point"): --("crl-distribution-point") +takes_value +multiple_occurrences "Adds a URL where the signer publishes its certificate revocation list. Replaces the ones from the signer's issuer URL file. May be repeated.")
+            (@arg ("ocsp-url"): --("ocsp-url") +takes_value +multiple_occurrences "Adds the URL of an OCSP responder for the signer to the authority information access. Replaces the ones from the signer's issuer URL file. May be repeated.")
+            (@arg ("ca-issuers-url"): --("ca-issuers-url") +takes_value +multiple_occurrences "Adds a URL where the signer certificate can be downloaded to the authority information access. Replaces the ones from the signer's issuer URL file. May be repeated.")
+            (@arg ("key-encoding"): --("key-encoding") requires("rekey") +takes_value possible_value[pkcs8 traditional] "Sets the structure of the created private key for both output formats: pkcs8, the default, or the key type specific SEC1 or PKCS#1 structure (ec and rsa keys only).")
+            (@arg ("export-public-key"): --("export-public-key") "Also writes the public key as a SubjectPublicKeyInfo structure, e.g. for pinning.")
+            (@arg ("encrypt-private-key"): --("encrypt-private-key") requires("rekey") "Encrypts the created private key as PKCS#8 with AES-256 and a passphrase taken from --passphrase-file, the TCH_PASSPHRASE environment variable, or a prompt.")
+            (@arg ("passphrase-file"): --("passphrase-file") requires("encrypt-private-key") +takes_value "A path to a file containing the passphrase used to encrypt the created private key.")
+            (@arg ("output-format"): --("output-format") +takes_value default_value[pem] possible_value[der pem] "Sets the output file format.")
+            (@arg ("key-type"): --("key-type") requires("rekey") +takes_value possible_value["ec" "ed25519" "ed448" "rsa" "rsa-pss"] "Sets the type of the created keys. Defaults to the type and size of the existing certificate's key.")
+            (@arg curve: --curve +takes_value default_value[p256] possible_value[p256 p384 p521 secp256k1] "Sets the elliptic curve of the created keys when --key-type is ec.")
+            (@arg ("key-size"): --("key-size") +takes_value default_value["2048"] "Sets the size in bits, from 2048 to 16384, of the created keys when --key-type is rsa or rsa-pss.")
+            (@arg ("public-exponent"): --("public-exponent") +takes_value default_value["65537"] "Sets the public exponent of the created keys when --key-type is rsa or rsa-pss.")
+            (@arg ("signature-hash"): --("signature-hash") +takes_value possible_value[sha256 sha384 sha512] "Sets the hash algorithm of the certificate signature. Defaults to one matching the signing key, e.g. sha256 for P-256 and sha384 for P-384. Ignored for Ed25519 and Ed448 signing keys.")
+            (@arg ("signature-scheme"): --("signature-scheme") +takes_value default_value[pkcs1v15] possible_value[pkcs1v15 pss] "Sets the padding of the certificate signature when the signing key is an RSA key. RSA-PSS signing keys always use pss.")
+            (@arg ("not-before"): --("not-before") +takes_value "Sets the start of the validity period to the given RFC 3339 timestamp, e.g. 2026-11-01T00:00:00Z. Defaults to now minus --backdate.")
+            (@arg backdate: --backdate +takes_value default_value["5m"] conflicts_with("not-before") "How long before now the validity period starts, so that devices whose clock is behind accept the certificate. Durations are a number followed by s, m, h, d or w, e.g. 90s or 1h30m.")
+            (@group validity =>
+                (@attributes +required)
+                (@arg ("days-valid"): --("days-valid") +takes_value "How may days from today, or from --not-before, the renewed certificate will be valid for.")
+                (@arg ("valid-for"): --("valid-for") +takes_value "How long from now, or from --not-before, the renewed certificate will be valid for, e.g. 12h or 90d.")
+                (@arg ("not-after"): --("not-after") +takes_value "Sets the end of the validity period to the given RFC 3339 timestamp.")
+                (@arg ("no-well-defined-expiry"): --("no-well-defined-expiry") "Sets the end of the validity period to 99991231235959Z, which RFC 5280 defines as no well-defined expiration date, e.g. for device identity certificates.")
+            )
+            (@arg ("output-directory"): --("output-directory") +takes_value default_value["."] "Sets the output directory.")
+            (@arg ("no-input"): --("no-input") "Runs the CLI in no-input mode.")
+            (@arg ("signer-certificate-path"): --("signer-certificate-path") +required +takes_value "A path to an existing pem or der encoded signer certificate to use.")
+            (@arg ("signer-private-key-path"): --("signer-private-key-path") +required +takes_value "A path to an existing pem or der encoded signer private key to use.")
+            (@arg ("signer-passphrase-file"): --("signer-passphrase-file") +takes_value "A path to a file containing the passphrase of an encrypted signer private key. Without it the passphrase is taken from the TCH_SIGNER_PASSPHRASE environment variable or a prompt.")
+            (@arg ("clamp-to-signer"): --("clamp-to-signer") "Shortens the validity period to end with the signer certificate's when it would otherwise outlive it, instead of refusing to create the certificate.")
         )
     )
 }
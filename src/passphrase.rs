@@ -0,0 +1,56 @@
+/*
+This file resolves the passphrases used to encrypt and decrypt private keys. A passphrase is taken
+from a file, from an environment variable, or from an interactive prompt, in that order.
+*/
+use crate::Error;
+use colored::*;
+use std::env;
+use std::fs;
+
+pub struct Source<'a> {
+    /// The name of the CLI option carrying the passphrase file path.
+    pub file_arg: &'static str,
+    pub file_path: Option<&'a str>,
+    pub env_var: &'static str,
+    pub prompt: &'static str,
+    /// Whether an interactive passphrase must be entered twice.
+    pub confirm: bool,
+}
+
+pub fn resolve(source: &Source, no_input: bool) -> Result<String, Error> {
+    let passphrase = if let Some(file_path) = source.file_path {
+        fs::read_to_string(file_path)?
+            .trim_end_matches(&['\r', '\n'][..])
+            .to_string()
+    } else if let Ok(passphrase) = env::var(source.env_var) {
+        passphrase
+    } else if no_input {
+        return Err(Error::PassphraseRequired {
+            file_arg: source.file_arg,
+            env_var: source.env_var,
+        });
+    } else {
+        prompt(source)?
+    };
+
+    if passphrase.is_empty() {
+        return Err(Error::EmptyPassphrase);
+    }
+
+    Ok(passphrase)
+}
+
+fn prompt(source: &Source) -> Result<String, Error> {
+    let passphrase = rpassword::prompt_password_stdout(&format!("{} ", source.prompt.magenta()))?;
+
+    if source.confirm {
+        let confirmation =
+            rpassword::prompt_password_stdout(&format!("{} ", "confirm passphrase:".magenta()))?;
+
+        if passphrase != confirmation {
+            return Err(Error::PassphraseMismatch);
+        }
+    }
+
+    Ok(passphrase)
+}
@@ -0,0 +1,395 @@
+/*
+Renewal of an existing certificate: a new serial number and validity period for the same subject,
+subject alternative names and extensions, either for the same key or for a new key pair.
+*/
+use crate::certificate;
+use crate::crypto;
+use crate::crypto::extensions;
+use crate::crypto::name;
+use crate::crypto::name_constraints::NameConstraints;
+use crate::crypto::san;
+use crate::Error;
+use chrono::{DateTime, Utc};
+use clap::ArgMatches;
+use colored::*;
+use openssl::pkey::{HasPublic, PKeyRef};
+use openssl::x509::X509;
+use std::path::PathBuf;
+
+pub struct Config {
+    certificate_path: PathBuf,
+    crypto_config: crypto::Config,
+    encrypt_private_key: bool,
+    no_input: bool,
+    output_directory: PathBuf,
+    private_key_path: Option<PathBuf>,
+    public_key_path: Option<PathBuf>,
+    renewed_certificate: X509,
+    renewed_certificate_path: PathBuf,
+    signer_issuer_urls_path: Option<PathBuf>,
+    signer_not_after: DateTime<Utc>,
+    /// The subject of the renewed certificate, which is copied as it is.
+    subject: String,
+    /// The number of subject alternative names that are copied without being checked against the
+    /// signer's name constraints, as they are of an unsupported type.
+    unsupported_subject_alt_names: usize,
+    validity_clamped: bool,
+}
+
+pub fn run(clap_matches: &ArgMatches) -> Result<(), Error> {
+    match create_config(clap_matches) {
+        Ok(config) => {
+            let credentials =
+                crypto::renew_certificate(&config.crypto_config, &config.renewed_certificate)?;
+
+            certificate::write_outputs(
+                vec![
+                    (config.private_key_path.as_ref(), credentials.private_key),
+                    (config.public_key_path.as_ref(), credentials.public_key),
+                    (
+                        Some(&config.certificate_path),
+                        Some(credentials.certificate),
+                    ),
+                ],
+                config.no_input,
+            )
+        }
+        Err(Error::CreateAborted) => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
+fn create_config(clap_matches: &ArgMatches) -> Result<Config, Error> {
+    let renewed_certificate_path =
+        PathBuf::from(clap_matches.value_of("certificate").unwrap()).canonicalize()?;
+    let renewed_certificate = crypto::load_certificate(&renewed_certificate_path)?;
+
+    // The subject and subject alternative names are copied as they are, they are only read for the
+    // preview, the file names and the signer's name constraints.
+    let subject = name::x509_name_to_string(renewed_certificate.subject_name());
+    let file_stem = certificate::certificate_file_stem(&renewed_certificate)?;
+
+    let (subject_alt_names, unsupported_subject_alt_names) =
+        san::supported_from_certificate(&renewed_certificate);
+
+    let mut validity = certificate::resolve_validity(clap_matches)?;
+
+    let output_directory = PathBuf::from(
+        clap_matches
+            .value_of("output-directory")
+            .unwrap()
+            .to_string(),
+    )
+    .canonicalize()?;
+
+    let output_format = certificate::resolve_output_format(clap_matches.value_of("output-format"));
+    let extension = output_format.to_string().to_lowercase();
+
+    // The renewed files are named apart from the ones they replace, which often sit in the same
+    // directory.
+    let prefix = match crypto::is_ca(&renewed_certificate) {
+        true => "-ca-renewed",
+        false => "-renewed",
+    };
+
+    let certificate_path =
+        output_directory.join(format!("{}{}-certificate.{}", file_stem, prefix, extension));
+
+    let export_public_key = clap_matches.is_present("export-public-key");
+
+    let public_key_path = match export_public_key {
+        true => {
+            Some(output_directory.join(format!("{}{}-public-key.{}", file_stem, prefix, extension)))
+        }
+        false => None,
+    };
+
+    let renewed_public_key = renewed_certificate.public_key()?;
+
+    let rekey = clap_matches.is_present("rekey");
+
+    let subject_key = match rekey {
+        true => crypto::SubjectKey::Generate(match clap_matches.value_of("key-type") {
+            Some(_) => certificate::resolve_key_type(clap_matches)?,
+            None => existing_key_type(&renewed_public_key)?,
+        }),
+        false => crypto::SubjectKey::PublicKey(renewed_public_key),
+    };
+
+    let private_key_path = match subject_key {
+        crypto::SubjectKey::Generate(_) => Some(
+            output_directory.join(format!("{}{}-private-key.{}", file_stem, prefix, extension)),
+        ),
+        _ => None,
+    };
+
+    // A private key that is overwritten is lost for good, unlike a certificate.
+    if let Some(private_key_path) = &private_key_path {
+        if private_key_path.exists() {
+            return Err(Error::PrivateKeyExists(private_key_path.clone()));
+        }
+    }
+
+    let signer_certificate_path =
+        PathBuf::from(clap_matches.value_of("signer-certificate-path").unwrap()).canonicalize()?;
+    let signer_private_key_path =
+        PathBuf::from(clap_matches.value_of("signer-private-key-path").unwrap()).canonicalize()?;
+
+    let signer_not_after = certificate::resolve_signer_not_after(&signer_certificate_path)?;
+
+    let validity_clamped = certificate::clamp_to_signer(
+        &mut validity,
+        signer_not_after,
+        clap_matches.is_present("clamp-to-signer"),
+    )?;
+
+    let (signer_issuer_urls_path, signer_issuer_urls) =
+        certificate::load_signer_issuer_urls(&signer_certificate_path)?;
+
+    // The key identifiers and issuer URLs describe the signer and key the certificate is issued
+    // with, they are built anew rather than copied. The subject key identifier still applies when
+    // the key is kept.
+    let copied_extensions = extensions::certificate_extensions(&renewed_certificate)
+        .into_iter()
+        .filter(|extension| match extension.oid.as_str() {
+            crypto::AUTHORITY_INFO_ACCESS_OID
+            | crypto::AUTHORITY_KEY_IDENTIFIER_OID
+            | crypto::CRL_DISTRIBUTION_POINTS_OID => false,
+            crypto::SUBJECT_KEY_IDENTIFIER_OID => !rekey,
+            _ => true,
+        })
+        .collect();
+
+    let mut config = Config {
+        certificate_path,
+        crypto_config: crypto::Config {
+            certificate_policies: Vec::new(),
+            custom_extensions: copied_extensions,
+            existing_private_key_passphrase: None,
+            export_public_key,
+            issuer_urls: certificate::resolve_issuer_urls(clap_matches, "", &signer_issuer_urls)?,
+            key_encoding: certificate::resolve_key_encoding(clap_matches.value_of("key-encoding")),
+            name_constraints: NameConstraints::default(),
+            output_format,
+            path_length: None,
+            private_key_passphrase: None,
+            profile: certificate::resolve_profile(None),
+            signature_hash: certificate::resolve_signature_hash(
+                clap_matches.value_of("signature-hash"),
+            ),
+            signature_scheme: certificate::resolve_signature_scheme(
+                clap_matches.value_of("signature-scheme"),
+            ),
+            signer_certificate_path: Some(signer_certificate_path),
+            signer_private_key_passphrase: None,
+            signer_private_key_path: Some(signer_private_key_path),
+            subject: None,
+            subject_alt_names,
+            subject_key,
+            self_signed: false,
+            validity,
+        },
+        encrypt_private_key: clap_matches.is_present("encrypt-private-key"),
+        no_input: clap_matches.is_present("no-input"),
+        output_directory,
+        private_key_path,
+        public_key_path,
+        renewed_certificate,
+        renewed_certificate_path,
+        signer_issuer_urls_path,
+        signer_not_after,
+        subject,
+        unsupported_subject_alt_names,
+        validity_clamped,
+    };
+
+    if let crypto::SubjectKey::Generate(key_type) = &config.crypto_config.subject_key {
+        certificate::check_key_encoding(
+            &config.crypto_config.key_encoding,
+            Some(key_type),
+            config.encrypt_private_key,
+        )?;
+    }
+
+    if !config.no_input {
+        verify_config(&config)?;
+    }
+
+    config.crypto_config.signer_private_key_passphrase =
+        certificate::resolve_signer_private_key_passphrase(
+            clap_matches,
+            config
+                .crypto_config
+                .signer_private_key_path
+                .as_ref()
+                .unwrap(),
+            config.no_input,
+        )?;
+
+    config.crypto_config.private_key_passphrase =
+        certificate::resolve_private_key_passphrase(clap_matches, config.no_input)?;
+
+    Ok(config)
+}
+
+/// The type and parameters of the existing key, for a new key like it. They must be ones tch would
+/// create keys with when asked to, e.g. not a 1024 bit RSA key.
+fn existing_key_type<T: HasPublic>(public_key: &PKeyRef<T>) -> Result<crypto::KeyType, Error> {
+    let key_type = crypto::key_type_of(public_key).ok_or(Error::UnsupportedKeyType)?;
+
+    if let crypto::KeyType::Rsa(rsa_parameters) | crypto::KeyType::RsaPss(rsa_parameters) =
+        &key_type
+    {
+        certificate::check_rsa_parameters(rsa_parameters)
+            .map_err(|err| Error::UnusableExistingKeyType(Box::new(err)))?;
+    }
+
+    Ok(key_type)
+}
+
+fn verify_config(config: &Config) -> Result<(), Error> {
+    let generated_key_type = match &config.crypto_config.subject_key {
+        crypto::SubjectKey::Generate(key_type) => Some(key_type),
+        _ => None,
+    };
+
+    print!(
+        "\
+A certificate will be renewed using the following configuration.
+
+renewed certificate path: {}
+subject: {}
+subject alternative names: {}
+copied extensions: {}
+crl distribution points: {}
+ocsp urls: {}
+ca issuers urls: {}
+signer issuer urls file: {}
+rekey: {}
+encrypt private key: {}
+key encoding: {}
+key type: {}
+signature hash: {}
+signature scheme: {}
+not before: {}
+not after: {}
+signer certificate path: {}
+signer private key path: {}
+signer not after: {}
+output directory: {}
+
+{} Double check \"not after\" above. Inconsiderate values can have devastating consequences.
+",
+        config.renewed_certificate_path.display().to_string().cyan(),
+        config.subject.cyan(),
+        certificate::format_subject_alt_names(
+            &config.crypto_config.subject_alt_names,
+            config.unsupported_subject_alt_names,
+            "not checked against the signer's name constraints",
+        )
+        .cyan(),
+        certificate::format_list(&config.crypto_config.custom_extensions).cyan(),
+        certificate::format_list(&config.crypto_config.issuer_urls.crl_distribution_points).cyan(),
+        certificate::format_list(&config.crypto_config.issuer_urls.ocsp).cyan(),
+        certificate::format_list(&config.crypto_config.issuer_urls.ca_issuers).cyan(),
+        config
+            .signer_issuer_urls_path
+            .as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| "none".to_string())
+            .cyan(),
+        generated_key_type.is_some().to_string().cyan(),
+        config.encrypt_private_key.to_string().cyan(),
+        match generated_key_type {
+            Some(_) => config.crypto_config.key_encoding.to_string(),
+            None => "not applicable".to_string(),
+        }
+        .cyan(),
+        match generated_key_type {
+            Some(crypto::KeyType::Ec(curve)) => format!("ec {}", curve),
+            Some(crypto::KeyType::Rsa(rsa_parameters))
+            | Some(crypto::KeyType::RsaPss(rsa_parameters)) => format!(
+                "{} {}",
+                generated_key_type.unwrap().to_string().to_lowercase(),
+                rsa_parameters.key_size
+            ),
+            Some(key_type) => key_type.to_string().to_lowercase(),
+            None => "not applicable".to_string(),
+        }
+        .cyan(),
+        config
+            .crypto_config
+            .signature_hash
+            .map(|signature_hash| signature_hash.to_string())
+            .unwrap_or_else(|| "matching the signing key".to_string())
+            .cyan(),
+        config.crypto_config.signature_scheme.to_string().cyan(),
+        certificate::format_timestamp(config.crypto_config.validity.not_before).cyan(),
+        certificate::format_not_after(&config.crypto_config.validity).cyan(),
+        config
+            .crypto_config
+            .signer_certificate_path
+            .as_ref()
+            .unwrap()
+            .display()
+            .to_string()
+            .cyan(),
+        config
+            .crypto_config
+            .signer_private_key_path
+            .as_ref()
+            .unwrap()
+            .display()
+            .to_string()
+            .cyan(),
+        certificate::format_timestamp(config.signer_not_after).cyan(),
+        config.output_directory.display().to_string().cyan(),
+        "WARNING".yellow(),
+    );
+
+    if config.validity_clamped {
+        println!(
+            "{} The validity period was shortened to end with the signer certificate's.",
+            "WARNING".yellow()
+        );
+    }
+
+    certificate::confirm(&[
+        config.private_key_path.as_ref(),
+        config.public_key_path.as_ref(),
+        Some(&config.certificate_path),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::bn::BigNum;
+    use openssl::pkey::PKey;
+    use openssl::rsa::Rsa;
+
+    fn rsa_key(key_size: u32, public_exponent: u32) -> PKey<openssl::pkey::Private> {
+        let public_exponent = BigNum::from_u32(public_exponent).unwrap();
+        let rsa = Rsa::generate_with_e(key_size, &public_exponent).unwrap();
+        PKey::from_rsa(rsa).unwrap()
+    }
+
+    #[test]
+    fn existing_key_type_keeps_acceptable_rsa_parameters() {
+        match existing_key_type(&rsa_key(2048, 65537)).unwrap() {
+            crypto::KeyType::Rsa(rsa_parameters) => {
+                assert_eq!(rsa_parameters.key_size, 2048);
+                assert_eq!(rsa_parameters.public_exponent, 65537);
+            }
+            key_type => panic!("unexpected key type {}", key_type),
+        }
+    }
+
+    #[test]
+    fn existing_key_type_refuses_small_rsa_keys() {
+        assert!(matches!(
+            existing_key_type(&rsa_key(1024, 65537)),
+            Err(Error::UnusableExistingKeyType(err)) if matches!(*err, Error::InvalidKeySize(1024))
+        ));
+    }
+}